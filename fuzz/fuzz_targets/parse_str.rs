@@ -0,0 +1,27 @@
+#![no_main]
+
+use chrono::{DateTime, FixedOffset, Utc};
+use libfuzzer_sys::fuzz_target;
+use relative_time_str::{parse_bytes_with_now, parse_seconds, parse_str_delta, parse_str_with_now};
+
+fuzz_target!(|text: &str| {
+    for now in [
+        DateTime::<Utc>::MIN_UTC,
+        DateTime::UNIX_EPOCH,
+        DateTime::<Utc>::MAX_UTC,
+    ] {
+        let _ = parse_str_with_now(text, now);
+        // the wall clock of these offsets is past either end of the range
+        for offset in [FixedOffset::west_opt(86_399), FixedOffset::east_opt(86_399)] {
+            let now = now.with_timezone(&offset.unwrap());
+            let _ = parse_str_with_now(text, now);
+            let _ = parse_str_delta(text, now);
+        }
+    }
+    let _ = parse_str_with_now(text, std::time::Duration::ZERO);
+    for now in [i128::MIN, 0, i128::MAX] {
+        let _ = parse_str_with_now(text, now);
+    }
+    let _ = parse_bytes_with_now(text.as_bytes(), std::time::Duration::ZERO);
+    let _ = parse_seconds(text);
+});
@@ -0,0 +1,68 @@
+use std::ops::Sub;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeComponents {
+    pub years: i64,
+    pub months: i64,
+    pub days: i64,
+    pub hours: i64,
+    pub minutes: i64,
+    pub seconds: i64,
+    pub nanos: i64,
+}
+
+/// Each component saturates rather than overflows, which only components set
+/// by hand can get near.
+impl Sub for TimeComponents {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            years: self.years.saturating_sub(rhs.years),
+            months: self.months.saturating_sub(rhs.months),
+            days: self.days.saturating_sub(rhs.days),
+            hours: self.hours.saturating_sub(rhs.hours),
+            minutes: self.minutes.saturating_sub(rhs.minutes),
+            seconds: self.seconds.saturating_sub(rhs.seconds),
+            nanos: self.nanos.saturating_sub(rhs.nanos),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sub() {
+        let lhs = TimeComponents {
+            years: 2023,
+            months: 9,
+            days: 1,
+            hours: 7,
+            minutes: 40,
+            seconds: 0,
+            nanos: 0,
+        };
+        let rhs = TimeComponents {
+            years: 2023,
+            months: 8,
+            days: 31,
+            hours: 5,
+            minutes: 40,
+            seconds: 30,
+            nanos: 0,
+        };
+
+        assert_eq!(
+            lhs - rhs,
+            TimeComponents {
+                months: 1,
+                days: -30,
+                hours: 2,
+                seconds: -30,
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -1,44 +1,202 @@
-use crate::{lexer::Token, parser::Expression};
+use crate::{
+    TimeComponents,
+    lexer::{Lexer, Token},
+    parser::{Expression, Parser, Unit, Weekday},
+};
 
 pub type Result<T, E = Error> = core::result::Result<T, E>;
 
-#[derive(Debug, thiserror::Error, Clone, Eq, PartialEq)]
+// Errors are `Copy`, so creating and cloning them never allocates.
+#[derive(Debug, thiserror::Error, Clone, Copy, Eq, PartialEq)]
 pub enum Error {
     #[error("unexpected character '{1}' at position {0}")]
     UnexpectedCharacter(usize, char),
-    #[error("number {0} is not valid: {1}")]
-    InvalidNumber(String, std::num::ParseIntError),
+    #[error("unexpected byte {1:#04x} at position {0}")]
+    UnexpectedByte(usize, u8),
+    #[error("unit '{1}' is lowercase, found an uppercase unit at position {0}")]
+    WrongCaseUnit(usize, char),
+    #[error("number at position {0}..{1} is too large")]
+    InvalidNumber(usize, usize),
     #[error("unexpected token at position {0}: expected {1}, found {2}")]
     InvalidFormat(usize, TokenType, TokenType),
+    #[error("unexpected end of input at position {0}: expected {1}")]
+    UnexpectedEndOfInput(usize, TokenType),
     #[error("floor operation may not be done before 'now'")]
     FloorBeforeNow,
     #[error("'now' should occur once")]
     MissingNow,
-    #[error("'now' cannot occur more than once")]
-    MultipleNow,
+    #[error("'now' cannot occur more than once, found another at position {0}")]
+    MultipleNow(usize),
+    #[error("'now' should be the first expression, found expression at position {0}")]
+    NowNotFirst(usize),
+    #[error("'now' is not allowed, only offsets, found one at position {0}")]
+    NowNotAllowed(usize),
+    #[error("no expressions were given")]
+    NoExpressions,
     #[error("expression '{0}' is unsupported")]
     UnsupportedExpression(Expression),
+    #[error("unit '{0}' is unsupported")]
+    UnsupportedUnit(Unit),
+    #[error("floor to unit '{0}' is not allowed")]
+    UnsupportedFloor(Unit),
+    #[error("the time at position {0} is past the start of the {1}")]
+    AlreadyPastBoundary(usize, Unit),
+    #[error("the month has no {1} number {0}")]
+    NoSuchWeekday(u32, Weekday),
+    #[error("offset of {2} {1}(s) at position {0} is too large")]
+    OffsetTooLarge(usize, Unit, u64),
     #[error("the given time delta is invalid")]
     InvalidDelta,
+    #[error("the computed duration is negative")]
+    NegativeDuration,
+    #[error("nanoseconds are unsupported")]
+    UnsupportedNanos,
     #[error("the computed date value is invalid")]
     InvalidTimestamp,
+    #[error("expression '{1}' at position {0} is out of range")]
+    OverflowAt(usize, Expression),
+    #[error("the base timestamp is not valid RFC 3339")]
+    InvalidBaseTimestamp,
+    #[error("unmatched brace at position {0}")]
+    UnmatchedBrace(usize),
+    #[error("business hours should open before they close, within a day")]
+    InvalidBusinessHours,
+    #[error(
+        "the local time {}-{:02}-{:02} {:02}:{:02}:{:02} occurs twice",
+        .0.years, .0.months, .0.days, .0.hours, .0.minutes, .0.seconds
+    )]
+    AmbiguousLocalTime(TimeComponents),
+}
+
+/// The stage of resolving a text an [`Error`] comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The text has a character or number that is not a token.
+    Lex,
+    /// The tokens do not form a valid expression.
+    Parse,
+    /// The expression is valid, but could not be applied to the time.
+    Resolve,
+}
+
+/// An [`Error`] of [`resolve_checked`](crate::resolve_checked), with its stage
+/// and the byte range `start..end` of the text it is about. Errors without a
+/// position cover the whole text, and running out of input is the empty range
+/// at its end.
+#[derive(Debug, thiserror::Error, Clone, Copy, Eq, PartialEq)]
+#[error("{error}")]
+pub struct ResolveError {
+    pub kind: ErrorKind,
+    pub start: usize,
+    pub end: usize,
+    pub error: Error,
+}
+
+impl ResolveError {
+    pub(crate) fn new(text: &str, error: Error) -> Self {
+        let whole = (0, text.len());
+        let (kind, (start, end)) = match error {
+            Error::UnexpectedCharacter(index, c) | Error::WrongCaseUnit(index, c) => {
+                (ErrorKind::Lex, (index, index + c.len_utf8()))
+            }
+            Error::UnexpectedByte(index, _) => (ErrorKind::Lex, (index, index + 1)),
+            Error::InvalidNumber(start, end) => (ErrorKind::Lex, (start, end)),
+            Error::InvalidFormat(index, ..)
+            | Error::OffsetTooLarge(index, ..)
+            | Error::UnmatchedBrace(index) => (ErrorKind::Parse, token_span(text, index)),
+            Error::UnexpectedEndOfInput(index, _) => (ErrorKind::Parse, (index, index)),
+            Error::MultipleNow(index) | Error::NowNotFirst(index) | Error::NowNotAllowed(index) => {
+                (ErrorKind::Parse, expression_span(text, index))
+            }
+            Error::FloorBeforeNow | Error::MissingNow | Error::NoExpressions => {
+                (ErrorKind::Parse, whole)
+            }
+            Error::OverflowAt(index, _) | Error::AlreadyPastBoundary(index, _) => {
+                (ErrorKind::Resolve, expression_span(text, index))
+            }
+            Error::UnsupportedExpression(_)
+            | Error::UnsupportedUnit(_)
+            | Error::UnsupportedFloor(_)
+            | Error::NoSuchWeekday(..)
+            | Error::InvalidDelta
+            | Error::NegativeDuration
+            | Error::UnsupportedNanos
+            | Error::InvalidTimestamp
+            | Error::InvalidBaseTimestamp
+            | Error::InvalidBusinessHours
+            | Error::AmbiguousLocalTime(_) => (ErrorKind::Resolve, whole),
+        };
+        // running out of input within a keyword is reported at the character
+        // past the end, which is the empty range at the end here
+        let start = start.min(text.len());
+        let end = end.clamp(start, text.len());
+        Self {
+            kind,
+            start,
+            end,
+            error,
+        }
+    }
+
+    pub fn span(&self) -> std::ops::Range<usize> {
+        self.start..self.end
+    }
+}
+
+// The token that starts at `index`.
+fn token_span(text: &str, index: usize) -> (usize, usize) {
+    let mut lexer = Lexer::new(text).starting_at(index);
+    match lexer.next() {
+        Some(Ok(_)) => (index, lexer.index()),
+        _ => (index, index),
+    }
+}
+
+// The expression that starts at `index`, up to the start of the next one.
+fn expression_span(text: &str, index: usize) -> (usize, usize) {
+    let mut parser = Parser::new(text);
+    let end = std::iter::from_fn(|| parser.next_spanned())
+        .map_while(Result::ok)
+        .map(|(start, _)| start)
+        .find(|start| *start > index)
+        .unwrap_or(text.len());
+    let end = text
+        .get(index..end)
+        .map_or(end, |expression| index + expression.trim_end().len());
+    (index, end)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TokenType {
     // all individual tokens
     Now,
+    Epoch,
     Value,
     Add,
     Sub,
     Floor,
+    AssertFloor,
+    Ago,
     Year,
+    IsoYear,
     Month,
     Week,
     Day,
     Hour,
     Minute,
     Second,
+    Millisecond,
+    This,
+    Next,
+    Last,
+    Weekday,
+    BusinessHours,
+    QuarterHour,
+    HalfHour,
+    At,
+    Repeat,
+    Jitter,
+    Whitespace,
     // additional tokentypes
     None,
     Operator,
@@ -49,17 +207,33 @@ impl std::fmt::Display for TokenType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Now => write!(f, "now"),
+            Self::Epoch => write!(f, "epoch"),
             Self::Value => write!(f, "number"),
             Self::Add => write!(f, "add"),
             Self::Sub => write!(f, "subtract"),
             Self::Floor => write!(f, "floor"),
+            Self::AssertFloor => write!(f, "assert floor"),
+            Self::Ago => write!(f, "ago"),
             Self::Year => write!(f, "year"),
+            Self::IsoYear => write!(f, "iso year"),
             Self::Month => write!(f, "month"),
             Self::Week => write!(f, "week"),
             Self::Day => write!(f, "day"),
             Self::Hour => write!(f, "hour"),
             Self::Minute => write!(f, "minute"),
             Self::Second => write!(f, "second"),
+            Self::Millisecond => write!(f, "millisecond"),
+            Self::This => write!(f, "this"),
+            Self::Next => write!(f, "next"),
+            Self::Last => write!(f, "last"),
+            Self::Weekday => write!(f, "weekday"),
+            Self::BusinessHours => write!(f, "business hours"),
+            Self::QuarterHour => write!(f, "quarter hour"),
+            Self::HalfHour => write!(f, "half hour"),
+            Self::At => write!(f, "at"),
+            Self::Repeat => write!(f, "repeat"),
+            Self::Jitter => write!(f, "jitter"),
+            Self::Whitespace => write!(f, "whitespace"),
             Self::None => write!(f, "nothing"),
             Self::Operator => write!(f, "operator"),
             Self::Unit => write!(f, "unit"),
@@ -71,17 +245,76 @@ impl From<Token> for TokenType {
     fn from(value: Token) -> Self {
         match value {
             Token::Now => Self::Now,
+            Token::Epoch => Self::Epoch,
             Token::Value(_) => Self::Value,
             Token::Add => Self::Add,
             Token::Sub => Self::Sub,
             Token::Floor => Self::Floor,
+            Token::AssertFloor => Self::AssertFloor,
+            Token::Ago => Self::Ago,
             Token::Year => Self::Year,
+            Token::IsoYear => Self::IsoYear,
             Token::Month => Self::Month,
             Token::Week => Self::Week,
             Token::Day => Self::Day,
             Token::Hour => Self::Hour,
             Token::Minute => Self::Minute,
             Token::Second => Self::Second,
+            Token::Millisecond => Self::Millisecond,
+            Token::This => Self::This,
+            Token::Next => Self::Next,
+            Token::Last => Self::Last,
+            Token::Weekday(_) => Self::Weekday,
+            Token::BusinessHours => Self::BusinessHours,
+            Token::QuarterHour => Self::QuarterHour,
+            Token::HalfHour => Self::HalfHour,
+            Token::At => Self::At,
+            Token::Repeat => Self::Repeat,
+            Token::Jitter => Self::Jitter,
+            Token::Whitespace(_) => Self::Whitespace,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_copy<T: Copy>(_: &T) {}
+
+    #[test]
+    fn allocation_free() {
+        let errors = [
+            Error::UnexpectedCharacter(0, 'x'),
+            Error::InvalidNumber(4, 14),
+            Error::InvalidFormat(0, TokenType::Operator, TokenType::Value),
+            Error::UnsupportedExpression(Expression::Floor(Unit::Day)),
+            Error::OffsetTooLarge(3, Unit::Year, 11),
+            Error::OverflowAt(3, Expression::Add(1, Unit::Day)),
+        ];
+        for error in errors {
+            assert_copy(&error);
+            assert_eq!(error.clone(), error);
         }
     }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            Error::InvalidNumber(4, 14).to_string(),
+            "number at position 4..14 is too large"
+        );
+        assert_eq!(
+            Error::UnexpectedEndOfInput(4, TokenType::Value).to_string(),
+            "unexpected end of input at position 4: expected number"
+        );
+        assert_eq!(
+            Error::UnexpectedByte(3, 0xc3).to_string(),
+            "unexpected byte 0xc3 at position 3"
+        );
+        assert_eq!(
+            Error::OverflowAt(3, Expression::Sub(1, Unit::Month)).to_string(),
+            "expression 'subtract month' at position 3 is out of range"
+        );
+    }
 }
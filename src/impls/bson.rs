@@ -0,0 +1,145 @@
+use bson::DateTime;
+use chrono::Utc;
+
+use crate::{RelativeTime, RelativeTimeNow, error::Result, parser::Weekday};
+
+// Resolves through the UTC backend of chrono, keeping the millisecond
+// precision of BSON. Results are limited to the range of chrono, roughly the
+// years -262143 to 262142, rather than that of the 64-bit milliseconds.
+fn utc(
+    time: DateTime,
+    op: impl FnOnce(chrono::DateTime<Utc>) -> Result<chrono::DateTime<Utc>>,
+) -> Result<DateTime> {
+    op(time.to_chrono()).map(DateTime::from_chrono)
+}
+
+impl RelativeTime for DateTime {
+    fn add_months(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.add_months(value))
+    }
+
+    fn add_days(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.add_days(value))
+    }
+
+    fn add_seconds(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.add_seconds(value))
+    }
+
+    fn add_millis(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.add_millis(value))
+    }
+
+    fn sub_months(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.sub_months(value))
+    }
+
+    fn sub_days(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.sub_days(value))
+    }
+
+    fn sub_seconds(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.sub_seconds(value))
+    }
+
+    fn sub_millis(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.sub_millis(value))
+    }
+
+    fn floor_years(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_years)
+    }
+
+    fn floor_months(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_months)
+    }
+
+    fn floor_weeks(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_weeks)
+    }
+
+    fn floor_days(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_days)
+    }
+
+    fn floor_hours(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_hours)
+    }
+
+    fn floor_minutes(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_minutes)
+    }
+
+    fn floor_seconds(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_seconds)
+    }
+
+    fn floor_millis(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_millis)
+    }
+
+    fn floor_iso_years(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_iso_years)
+    }
+
+    fn day_of_week(&self) -> Option<Weekday> {
+        self.to_chrono().day_of_week()
+    }
+
+    fn day_of_month(&self) -> Option<u32> {
+        self.to_chrono().day_of_month()
+    }
+
+    fn epoch(&self) -> Option<Self> {
+        Some(Self::from_millis(0))
+    }
+}
+
+impl RelativeTimeNow for DateTime {
+    fn now() -> Self {
+        Self::now()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_str_with_now;
+
+    #[test]
+    fn matches_utc() {
+        // 2023-08-21T05:40:00.123Z
+        let now = DateTime::from_millis(1_692_596_400_123);
+        for text in [
+            "now+1d",
+            "now-1M/d",
+            "now/w+2h",
+            "now/Y",
+            "now next fri",
+            "epoch+1d",
+            "now+500ms",
+            "now/ms",
+        ] {
+            assert_eq!(
+                parse_str_with_now(text, now),
+                parse_str_with_now(text, now.to_chrono()).map(DateTime::from_chrono),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            parse_str_with_now("now+1d", now),
+            Ok(DateTime::from_millis(1_692_682_800_123))
+        );
+        assert_eq!(
+            parse_str_with_now("now+500ms", now),
+            Ok(DateTime::from_millis(1_692_596_400_623))
+        );
+    }
+
+    #[test]
+    fn now() {
+        let before = DateTime::now();
+        let now = crate::parse_str::<DateTime>("now").expect("now should resolve");
+        assert!(now >= before);
+    }
+}
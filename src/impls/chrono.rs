@@ -1,20 +1,301 @@
-use chrono::{DateTime, Datelike, Days, DurationRound, Months, TimeDelta};
+use std::fmt::Display;
+
+use chrono::{
+    DateTime, Datelike, Days, MappedLocalTime, Months, NaiveDate, NaiveDateTime, NaiveTime, Offset,
+    SecondsFormat, TimeDelta, TimeZone, Timelike, Utc,
+};
 
 use crate::{
-    MONTHS_PER_YEAR, RelativeTime,
+    BusinessHours, ParseOptions, RelativeTime, TimeComponents,
     error::{Error, Result},
+    offset, parse_str_with_now,
+    parser::{Expression, Unit, Weekday},
+    resolve_in_text_with,
 };
 
-impl<Tz: chrono::TimeZone> RelativeTime for DateTime<Tz> {
+const NANOS_PER_SECOND: u32 = 1_000_000_000;
+const NANOS_PER_MILLI: u32 = 1_000_000;
+const NANOS_PER_MICRO: u32 = 1_000;
+
+// The local wall clock, which is out of range for the first and last hours of
+// `DateTime::MIN_UTC` and `DateTime::MAX_UTC` when the offset moves it past
+// them, where `DateTime::naive_local` would panic.
+fn local<Tz: TimeZone>(datetime: &DateTime<Tz>) -> Result<NaiveDateTime> {
+    datetime
+        .naive_utc()
+        .checked_add_offset(datetime.offset().fix())
+        .ok_or(Error::InvalidTimestamp)
+}
+
+// Floors are done on the local wall clock and resolved again in the time zone
+// of the original timestamp, so zones with daylight saving time get their own
+// midnight. When the clocks were turned back the floored wall clock occurs
+// twice, of which the last one that is not past the original is taken. One that
+// was skipped is resolved like in `to_local`, so the start of the gap ends up at
+// the first instant after it.
+fn floor_to_local<Tz: TimeZone>(
+    datetime: &DateTime<Tz>,
+    floored: Option<NaiveDateTime>,
+) -> Result<DateTime<Tz>> {
+    let floored = floored.ok_or(Error::InvalidTimestamp)?;
+    match datetime.timezone().from_local_datetime(&floored) {
+        MappedLocalTime::Single(floored) => Ok(floored),
+        MappedLocalTime::Ambiguous(earliest, latest) => Ok(if latest <= *datetime {
+            latest
+        } else {
+            earliest
+        }),
+        MappedLocalTime::None => from_skipped_local(&datetime.timezone(), floored),
+    }
+}
+
+// Calendar units move the local wall clock, which is resolved again in the
+// time zone of the original timestamp. A wall clock that occurs twice takes
+// the earliest, and one that was skipped is taken in the offset from before
+// the clocks moved forward, so it ends up later by the length of the gap.
+fn to_local<Tz: TimeZone>(
+    datetime: &DateTime<Tz>,
+    moved: Option<NaiveDateTime>,
+) -> Result<DateTime<Tz>> {
+    let moved = moved.ok_or(Error::InvalidTimestamp)?;
+    let timezone = datetime.timezone();
+    match timezone.from_local_datetime(&moved) {
+        MappedLocalTime::Single(moved) | MappedLocalTime::Ambiguous(moved, _) => Ok(moved),
+        MappedLocalTime::None => from_skipped_local(&timezone, moved),
+    }
+}
+
+// Resolves a wall clock that was skipped in the offset from a day earlier.
+fn from_skipped_local<Tz: TimeZone>(timezone: &Tz, local: NaiveDateTime) -> Result<DateTime<Tz>> {
+    let offset = local
+        .checked_sub_days(Days::new(1))
+        .and_then(|before| timezone.offset_from_local_datetime(&before).earliest())
+        .ok_or(Error::InvalidTimestamp)?;
+    local
+        .checked_sub_offset(offset.fix())
+        .map(|utc| timezone.from_utc_datetime(&utc))
+        .ok_or(Error::InvalidTimestamp)
+}
+
+/// Resolves `expr` relative to the RFC 3339 timestamp `base`, formatted back
+/// in the offset of `base`, e.g. `now+1d` on `2023-08-21T05:40:00Z` yields
+/// `2023-08-22T05:40:00Z`. An invalid `base` results in
+/// [`Error::InvalidBaseTimestamp`], any other error comes from `expr`.
+pub fn apply_to_rfc3339(base: &str, expr: &str) -> Result<String> {
+    let now = DateTime::parse_from_rfc3339(base).map_err(|_err| Error::InvalidBaseTimestamp)?;
+    let time = parse_str_with_now(expr, now)?;
+    Ok(time.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+}
+
+/// Replaces every `{expression}` in `template` by the expression resolved
+/// relative to `now`, as an RFC 3339 timestamp in the offset of `now`, see
+/// [`resolve_in_text_with`] for other formats.
+pub fn resolve_in_text<Tz: TimeZone>(template: &str, now: &DateTime<Tz>) -> Result<String>
+where
+    Tz::Offset: Display,
+{
+    resolve_in_text_with(template, now, |time| {
+        time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+    })
+}
+
+/// Resolves `text` relative to `now` in the time zone `zone`, so floors are to
+/// the local midnight, hour and so on of `zone` rather than of UTC, e.g.
+/// `now/d` in `+05:30` on `2023-08-21T20:00:00Z` yields
+/// `2023-08-22T00:00:00+05:30`.
+pub fn parse_str_in_zone<Tz: TimeZone>(
+    text: &str,
+    now: DateTime<Utc>,
+    zone: &Tz,
+) -> Result<DateTime<Tz>> {
+    parse_str_with_now(text, now.with_timezone(zone))
+}
+
+/// Resolves `text` relative to `now` as the exact [`TimeDelta`] from `now`,
+/// e.g. to show "in 2 hours". Calendar units and floors become the length
+/// they have for the dates involved, so `now+1M` in January is 31 days.
+pub fn parse_str_signed_delta<Tz: TimeZone>(text: &str, now: DateTime<Tz>) -> Result<TimeDelta> {
+    let time = parse_str_with_now(text, now.clone())?;
+    Ok(time.signed_duration_since(now))
+}
+
+/// The time from `time` until the start of the next `unit`, e.g. 20 minutes
+/// from 05:40 until the next hour, to schedule the next tick. On a boundary
+/// this is a full unit until the next one, never zero. Weeks start on monday,
+/// and days are as long as they are in the time zone of `time`.
+pub fn duration_until_next<Tz: TimeZone>(time: &DateTime<Tz>, unit: Unit) -> Result<TimeDelta> {
+    let (floored, _) = time.clone().floor_checked(unit)?;
+    let next = offset(floored, Expression::Add(1, unit), ParseOptions::default())?;
+    Ok(next.signed_duration_since(time))
+}
+
+/// The time until the next hour, see [`duration_until_next`].
+pub fn duration_until_next_hour<Tz: TimeZone>(time: &DateTime<Tz>) -> Result<TimeDelta> {
+    duration_until_next(time, Unit::Hour)
+}
+
+/// The time until the next midnight, see [`duration_until_next`].
+pub fn duration_until_next_day<Tz: TimeZone>(time: &DateTime<Tz>) -> Result<TimeDelta> {
+    duration_until_next(time, Unit::Day)
+}
+
+/// The time until the start of next week, see [`duration_until_next`].
+pub fn duration_until_next_week<Tz: TimeZone>(time: &DateTime<Tz>) -> Result<TimeDelta> {
+    duration_until_next(time, Unit::Week)
+}
+
+/// Every start of a `unit` strictly between `now` and `target`, e.g. each
+/// midnight crossed, to highlight them in a calendar. They are ordered from
+/// `now` towards `target`, so descending when `target` is before `now`.
+pub fn boundaries_between<Tz: TimeZone>(
+    now: &DateTime<Tz>,
+    target: &DateTime<Tz>,
+    unit: Unit,
+) -> Result<Vec<DateTime<Tz>>> {
+    let (start, end) = if now <= target {
+        (now, target)
+    } else {
+        (target, now)
+    };
+    let mut boundaries = Vec::new();
+    let (mut boundary, _) = start.clone().floor_checked(unit)?;
+    loop {
+        // floored again, as calendar units keep the wall clock of a boundary
+        // the clocks skipped
+        let next = offset(boundary, Expression::Add(1, unit), ParseOptions::default())?;
+        (boundary, _) = next.floor_checked(unit)?;
+        if boundary >= *end {
+            break;
+        }
+        boundaries.push(boundary.clone());
+    }
+    if now > target {
+        boundaries.reverse();
+    }
+    Ok(boundaries)
+}
+
+/// Describes `result` relative to `now` for display, like "3 hours ago" or
+/// "in 2 days", in the largest unit that is at least one, rounded to the
+/// nearest. Months are 30 days and years 365 days, and anything within half a
+/// second of `now` is "now".
+pub fn humanize<Tz: TimeZone>(result: &DateTime<Tz>, now: &DateTime<Tz>) -> String {
+    // the size of each unit in seconds, and how many of them make the next
+    const UNITS: [(&str, u64, u64); 6] = [
+        ("second", 1, 60),
+        ("minute", 60, 60),
+        ("hour", 60 * 60, 24),
+        ("day", 24 * 60 * 60, 30),
+        ("month", 30 * 24 * 60 * 60, 12),
+        ("year", 365 * 24 * 60 * 60, u64::MAX),
+    ];
+
+    let delta = result.clone().signed_duration_since(now);
+    let seconds = delta.num_milliseconds().unsigned_abs().saturating_add(500) / 1000;
+    if seconds == 0 {
+        return "now".to_string();
+    }
+    let (mut unit, mut count) = ("second", seconds);
+    for (name, size, next) in UNITS {
+        (unit, count) = (name, (seconds + size / 2) / size);
+        if count < next {
+            break;
+        }
+    }
+    let plural = if count == 1 { "" } else { "s" };
+    if delta < TimeDelta::zero() {
+        format!("{count} {unit}{plural} ago")
+    } else {
+        format!("in {count} {unit}{plural}")
+    }
+}
+
+/// The difference from `base` to `target` as an ISO 8601 duration, like
+/// `P1Y2M3DT4H5M6.5S`. Whole months are counted first, the way `now+1M` adds
+/// them, and the rest is exact, so resolving `now+14M+3d+4h+5m+6s+500ms` from
+/// `base` yields `target`, with each year as 12 months. Adding the years on
+/// their own, like `now+1y+2M`, can end up a day off, as both steps clamp to
+/// the end of a month. A `target` before `base` gives a leading `-`, with the
+/// months counted back from `base`.
+pub fn to_iso8601_offset<Tz: TimeZone>(
+    base: &DateTime<Tz>,
+    target: &DateTime<Tz>,
+) -> Result<String> {
+    const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+    let negative = target < base;
+    // the month of `target` is never passed, so this is at most one too many
+    let components = TimeComponents::from(target) - TimeComponents::from(base);
+    let estimate = components
+        .years
+        .saturating_mul(12)
+        .saturating_add(components.months);
+    let mut months = u32::try_from(estimate.unsigned_abs()).map_err(|_err| Error::InvalidDelta)?;
+    let rest = loop {
+        let rest = if negative {
+            base.clone().sub_months(months)? - target.clone()
+        } else {
+            target.clone() - base.clone().add_months(months)?
+        };
+        if rest >= TimeDelta::zero() || months == 0 {
+            break rest;
+        }
+        months -= 1;
+    };
+
+    let seconds = rest.num_seconds();
+    let nanos = rest.subsec_nanos();
+    let mut iso = String::from(if negative { "-P" } else { "P" });
+    for (value, unit) in [
+        (i64::from(months / 12), 'Y'),
+        (i64::from(months % 12), 'M'),
+        (seconds / SECONDS_PER_DAY, 'D'),
+    ] {
+        if value != 0 {
+            iso.push_str(&format!("{value}{unit}"));
+        }
+    }
+    let mut time = String::new();
+    for (value, unit) in [
+        (seconds % SECONDS_PER_DAY / 3600, 'H'),
+        (seconds % 3600 / 60, 'M'),
+    ] {
+        if value != 0 {
+            time.push_str(&format!("{value}{unit}"));
+        }
+    }
+    if seconds % 60 != 0 || nanos != 0 {
+        time.push_str(&(seconds % 60).to_string());
+        if nanos != 0 {
+            time.push_str(format!(".{nanos:09}").trim_end_matches('0'));
+        }
+        time.push('S');
+    }
+    if !time.is_empty() {
+        iso.push('T');
+        iso.push_str(&time);
+    } else if iso.ends_with('P') {
+        iso.push_str("T0S");
+    }
+    Ok(iso)
+}
+
+/// Timestamps between [`DateTime::MIN_UTC`] and [`DateTime::MAX_UTC`], roughly
+/// the years -262143 to 262142. Going past either end, on the UTC timeline or
+/// on the local wall clock, results in [`Error::InvalidTimestamp`].
+///
+/// Years, months, weeks and days keep the local wall clock across changes to
+/// daylight saving time, so `now+1M` at noon is at noon again even when the
+/// month is an hour shorter. Hours and smaller units are exact durations.
+impl<Tz: TimeZone> RelativeTime for DateTime<Tz> {
     fn add_months(self, value: u32) -> Result<Self> {
-        let delta = Months::new(value);
-        self.checked_add_months(delta)
-            .ok_or(Error::InvalidTimestamp)
+        let moved = local(&self)?.checked_add_months(Months::new(value));
+        to_local(&self, moved)
     }
 
     fn add_days(self, value: u32) -> Result<Self> {
-        let delta = Days::new(value.into());
-        self.checked_add_days(delta).ok_or(Error::InvalidTimestamp)
+        let moved = local(&self)?.checked_add_days(Days::new(value.into()));
+        to_local(&self, moved)
     }
 
     fn add_seconds(self, value: u32) -> Result<Self> {
@@ -24,14 +305,13 @@ impl<Tz: chrono::TimeZone> RelativeTime for DateTime<Tz> {
     }
 
     fn sub_months(self, value: u32) -> Result<Self> {
-        let delta = Months::new(value);
-        self.checked_sub_months(delta)
-            .ok_or(Error::InvalidTimestamp)
+        let moved = local(&self)?.checked_sub_months(Months::new(value));
+        to_local(&self, moved)
     }
 
     fn sub_days(self, value: u32) -> Result<Self> {
-        let delta = Days::new(value.into());
-        self.checked_sub_days(delta).ok_or(Error::InvalidTimestamp)
+        let moved = local(&self)?.checked_sub_days(Days::new(value.into()));
+        to_local(&self, moved)
     }
 
     fn sub_seconds(self, value: u32) -> Result<Self> {
@@ -40,52 +320,1123 @@ impl<Tz: chrono::TimeZone> RelativeTime for DateTime<Tz> {
             .ok_or(Error::InvalidTimestamp)
     }
 
+    fn sub_nanos(self, value: u32) -> Result<Self> {
+        self.checked_sub_signed(TimeDelta::nanoseconds(value.into()))
+            .ok_or(Error::InvalidTimestamp)
+    }
+
+    fn add_nanos(self, value: u32) -> Result<Self> {
+        self.checked_add_signed(TimeDelta::nanoseconds(value.into()))
+            .ok_or(Error::InvalidTimestamp)
+    }
+
+    fn add_millis(self, value: u32) -> Result<Self> {
+        self.checked_add_signed(TimeDelta::milliseconds(value.into()))
+            .ok_or(Error::InvalidTimestamp)
+    }
+
+    fn sub_millis(self, value: u32) -> Result<Self> {
+        self.checked_sub_signed(TimeDelta::milliseconds(value.into()))
+            .ok_or(Error::InvalidTimestamp)
+    }
+
+    fn add_micros(self, value: u32) -> Result<Self> {
+        self.checked_add_signed(TimeDelta::microseconds(value.into()))
+            .ok_or(Error::InvalidTimestamp)
+    }
+
+    fn sub_micros(self, value: u32) -> Result<Self> {
+        self.checked_sub_signed(TimeDelta::microseconds(value.into()))
+            .ok_or(Error::InvalidTimestamp)
+    }
+
     fn floor_years(self) -> Result<Self> {
-        let offset = self.offset().clone();
-        let years = self
-            .to_utc()
-            .years_since(Self::MIN_UTC)
-            .expect("min is used as base");
-        let datetime = chrono::NaiveDateTime::MIN + Months::new(years * MONTHS_PER_YEAR);
-        Ok(Self::from_naive_utc_and_offset(datetime, offset))
+        // the day goes first, so the month is never left on a day it lacks
+        let floored = local(&self)?
+            .with_day0(0)
+            .and_then(|local| local.with_month0(0))
+            .map(|local| local.date().and_time(NaiveTime::MIN));
+        floor_to_local(&self, floored)
     }
 
     fn floor_months(self) -> Result<Self> {
-        let offset = self.offset().clone();
-        let utc = self.to_utc();
-        let years = utc.years_since(Self::MIN_UTC).expect("min is used as base");
-        let months = utc.month0();
-        let datetime = chrono::NaiveDateTime::MIN + Months::new(years * MONTHS_PER_YEAR + months);
-        Ok(Self::from_naive_utc_and_offset(datetime, offset))
+        let floored = local(&self)?
+            .with_day0(0)
+            .map(|local| local.date().and_time(NaiveTime::MIN));
+        floor_to_local(&self, floored)
     }
 
     fn floor_weeks(self) -> Result<Self> {
-        let delta = TimeDelta::weeks(1);
-        self.duration_trunc(delta)
-            .map_err(|_err| Error::InvalidTimestamp)
+        let local = local(&self)?;
+        let days = local.weekday().num_days_from_monday();
+        let floored = local
+            .date()
+            .checked_sub_days(Days::new(days.into()))
+            .map(|date| date.and_time(NaiveTime::MIN));
+        floor_to_local(&self, floored)
+    }
+
+    fn floor_iso_years(self) -> Result<Self> {
+        let year = local(&self)?.iso_week().year();
+        let floored = NaiveDate::from_isoywd_opt(year, 1, chrono::Weekday::Mon)
+            .map(|date| date.and_time(NaiveTime::MIN));
+        floor_to_local(&self, floored)
     }
 
     fn floor_days(self) -> Result<Self> {
-        let delta = TimeDelta::days(1);
-        self.duration_trunc(delta)
-            .map_err(|_err| Error::InvalidTimestamp)
+        let local = local(&self)?;
+        let floored = local.date().and_time(NaiveTime::MIN);
+        floor_to_local(&self, Some(floored))
+    }
+
+    fn floor_business_hours(self, hours: BusinessHours) -> Result<Self> {
+        let time = |(hour, minute)| NaiveTime::from_hms_opt(hour, minute, 0);
+        let (open, close) = time(hours.open())
+            .zip(time(hours.close()))
+            .ok_or(Error::InvalidTimestamp)?;
+        let is_business_day = |date: &NaiveDate| date.weekday().num_days_from_monday() < 5;
+        let local = local(&self)?;
+        let (date, time) = (local.date(), local.time());
+        if is_business_day(&date) && open <= time && time < close {
+            return floor_to_local(&self, Some(date.and_time(open)));
+        }
+        // after closing it is today's close, otherwise that of an earlier day
+        let mut day = date;
+        if !is_business_day(&day) || time < close {
+            day = day.pred_opt().ok_or(Error::InvalidTimestamp)?;
+            while !is_business_day(&day) {
+                day = day.pred_opt().ok_or(Error::InvalidTimestamp)?;
+            }
+        }
+        floor_to_local(&self, Some(day.and_time(close)))
+    }
+
+    fn floor_minutes_by(self, minutes: u32) -> Result<Self> {
+        let local = local(&self)?;
+        let minute = local
+            .minute()
+            .checked_rem(minutes)
+            .map(|rem| local.minute() - rem)
+            .ok_or(Error::InvalidDelta)?;
+        let floored = NaiveTime::from_hms_opt(local.hour(), minute, 0)
+            .map(|time| local.date().and_time(time));
+        floor_to_local(&self, floored)
+    }
+
+    fn nth_weekday_of_month(self, n: u32, weekday: Weekday) -> Result<Self> {
+        let local = local(&self)?;
+        let date = u8::try_from(n)
+            .ok()
+            .filter(|n| *n > 0)
+            .and_then(|n| {
+                NaiveDate::from_weekday_of_month_opt(local.year(), local.month(), weekday.into(), n)
+            })
+            .ok_or(Error::NoSuchWeekday(n, weekday))?;
+        floor_to_local(&self, Some(date.and_time(NaiveTime::MIN)))
+    }
+
+    fn ambiguous_local_time(&self) -> Option<TimeComponents> {
+        let local = local(self).ok()?;
+        matches!(
+            self.timezone().from_local_datetime(&local),
+            MappedLocalTime::Ambiguous(..)
+        )
+        .then(|| TimeComponents::from(self))
     }
 
     fn floor_hours(self) -> Result<Self> {
-        let delta = TimeDelta::hours(1);
-        self.duration_trunc(delta)
-            .map_err(|_err| Error::InvalidTimestamp)
+        let local = local(&self)?;
+        let floored =
+            NaiveTime::from_hms_opt(local.hour(), 0, 0).map(|time| local.date().and_time(time));
+        floor_to_local(&self, floored)
     }
 
     fn floor_minutes(self) -> Result<Self> {
-        let delta = TimeDelta::minutes(1);
-        self.duration_trunc(delta)
-            .map_err(|_err| Error::InvalidTimestamp)
+        let local = local(&self)?;
+        let floored = NaiveTime::from_hms_opt(local.hour(), local.minute(), 0)
+            .map(|time| local.date().and_time(time));
+        floor_to_local(&self, floored)
     }
 
     fn floor_seconds(self) -> Result<Self> {
-        let delta = TimeDelta::seconds(1);
-        self.duration_trunc(delta)
-            .map_err(|_err| Error::InvalidTimestamp)
+        // keeps a leap second, as that is the second the timestamp is in
+        let local = local(&self)?;
+        let nanos = local.nanosecond() / NANOS_PER_SECOND * NANOS_PER_SECOND;
+        floor_to_local(&self, local.with_nanosecond(nanos))
+    }
+
+    fn floor_millis(self) -> Result<Self> {
+        let local = local(&self)?;
+        let nanos = local.nanosecond() / NANOS_PER_MILLI * NANOS_PER_MILLI;
+        floor_to_local(&self, local.with_nanosecond(nanos))
+    }
+
+    fn floor_micros(self) -> Result<Self> {
+        let local = local(&self)?;
+        let nanos = local.nanosecond() / NANOS_PER_MICRO * NANOS_PER_MICRO;
+        floor_to_local(&self, local.with_nanosecond(nanos))
+    }
+
+    fn day_of_week(&self) -> Option<Weekday> {
+        Some(local(self).ok()?.weekday().into())
+    }
+
+    fn day_of_month(&self) -> Option<u32> {
+        Some(local(self).ok()?.day())
+    }
+
+    fn epoch(&self) -> Option<Self> {
+        Some(DateTime::UNIX_EPOCH.with_timezone(&self.timezone()))
+    }
+}
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(value: chrono::Weekday) -> Self {
+        match value {
+            chrono::Weekday::Mon => Self::Monday,
+            chrono::Weekday::Tue => Self::Tuesday,
+            chrono::Weekday::Wed => Self::Wednesday,
+            chrono::Weekday::Thu => Self::Thursday,
+            chrono::Weekday::Fri => Self::Friday,
+            chrono::Weekday::Sat => Self::Saturday,
+            chrono::Weekday::Sun => Self::Sunday,
+        }
+    }
+}
+
+impl From<Weekday> for chrono::Weekday {
+    fn from(value: Weekday) -> Self {
+        match value {
+            Weekday::Monday => Self::Mon,
+            Weekday::Tuesday => Self::Tue,
+            Weekday::Wednesday => Self::Wed,
+            Weekday::Thursday => Self::Thu,
+            Weekday::Friday => Self::Fri,
+            Weekday::Saturday => Self::Sat,
+            Weekday::Sunday => Self::Sun,
+        }
+    }
+}
+
+impl<Tz: TimeZone> From<&DateTime<Tz>> for TimeComponents {
+    fn from(value: &DateTime<Tz>) -> Self {
+        // a wall clock past either end is clamped, rather than panicking
+        let local = local(value).unwrap_or(if value.offset().fix().local_minus_utc() > 0 {
+            NaiveDateTime::MAX
+        } else {
+            NaiveDateTime::MIN
+        });
+        Self {
+            years: local.year().into(),
+            months: local.month().into(),
+            days: local.day().into(),
+            hours: local.hour().into(),
+            minutes: local.minute().into(),
+            seconds: local.second().into(),
+            nanos: local.nanosecond().into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::FixedOffset;
+
+    use super::*;
+    use crate::{error::TokenType, parse_str_with_options};
+
+    macro_rules! test_fixed_offset {
+        ($input:expr, $output:expr) => {
+            let offset = FixedOffset::east_opt(5 * 3600 + 30 * 60).expect("offset invalid");
+            let datetime = DateTime::parse_from_rfc3339("2023-08-01T02:15:30+05:30")
+                .expect("input date time invalid")
+                .with_timezone(&offset);
+
+            let res = parse_str_with_now($input, datetime).expect("input date time invalid");
+
+            assert_eq!(res.to_rfc3339(), $output);
+        };
+    }
+
+    #[test]
+    fn fixed_offset_floor_year() {
+        test_fixed_offset!("now/y", "2023-01-01T00:00:00+05:30");
+    }
+
+    #[test]
+    fn fixed_offset_floor_month() {
+        test_fixed_offset!("now/M", "2023-08-01T00:00:00+05:30");
+    }
+
+    #[test]
+    fn fixed_offset_floor_week() {
+        test_fixed_offset!("now/w", "2023-07-31T00:00:00+05:30");
+    }
+
+    #[test]
+    fn fixed_offset_floor_day() {
+        test_fixed_offset!("now/d", "2023-08-01T00:00:00+05:30");
+    }
+
+    #[test]
+    fn fixed_offset_floor_hour() {
+        test_fixed_offset!("now/h", "2023-08-01T02:00:00+05:30");
+    }
+
+    #[test]
+    fn fixed_offset_floor_minute() {
+        test_fixed_offset!("now/m", "2023-08-01T02:15:00+05:30");
+    }
+
+    #[test]
+    fn fixed_offset_floor_second() {
+        let datetime = DateTime::parse_from_rfc3339("2023-08-01T02:15:30.250+05:30")
+            .expect("input date time invalid");
+
+        let res = parse_str_with_now("now/s", datetime).expect("input date time invalid");
+
+        assert_eq!(res.to_rfc3339(), "2023-08-01T02:15:30+05:30");
+    }
+
+    macro_rules! test_leap_second {
+        ($input:expr, $output:expr) => {
+            let datetime = NaiveDate::from_ymd_opt(2016, 12, 31)
+                .and_then(|date| date.and_hms_milli_opt(23, 59, 59, 1_500))
+                .expect("input date time invalid")
+                .and_utc();
+
+            let res = parse_str_with_now($input, datetime).expect("input date time invalid");
+
+            assert_eq!(res.to_rfc3339_opts(SecondsFormat::Millis, true), $output);
+        };
+    }
+
+    #[test]
+    fn leap_second_offsets() {
+        test_leap_second!("now", "2016-12-31T23:59:60.500Z");
+        test_leap_second!("now+1s", "2017-01-01T00:00:00.500Z");
+        test_leap_second!("now-1s", "2016-12-31T23:59:59.500Z");
+    }
+
+    #[test]
+    fn leap_second_floors() {
+        test_leap_second!("now/s", "2016-12-31T23:59:60.000Z");
+        test_leap_second!("now/m", "2016-12-31T23:59:00.000Z");
+        test_leap_second!("now/h", "2016-12-31T23:00:00.000Z");
+        test_leap_second!("now/d", "2016-12-31T00:00:00.000Z");
+        test_leap_second!("now/M", "2016-12-01T00:00:00.000Z");
+    }
+
+    #[test]
+    fn floor_iso_year() {
+        let parse = |text, (year, month, day)| {
+            let datetime = NaiveDate::from_ymd_opt(year, month, day)
+                .and_then(|date| date.and_hms_opt(5, 40, 0))
+                .expect("input date time invalid")
+                .and_utc();
+            parse_str_with_now(text, datetime).map(|res| res.to_rfc3339())
+        };
+
+        // ISO week 1 of 2020 starts on monday 2019-12-30
+        assert_eq!(
+            parse("now/Y", (2020, 1, 1)),
+            Ok("2019-12-30T00:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            parse("now/Y", (2019, 12, 31)),
+            Ok("2019-12-30T00:00:00+00:00".to_string())
+        );
+        // ISO week 1 of 2019 starts on monday 2018-12-31
+        assert_eq!(
+            parse("now/Y", (2019, 12, 29)),
+            Ok("2018-12-31T00:00:00+00:00".to_string())
+        );
+        // ISO week 1 of 2021 starts on monday 2021-01-04
+        assert_eq!(
+            parse("now/Y", (2021, 1, 3)),
+            Ok("2019-12-30T00:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            parse("now/y", (2020, 1, 1)),
+            Ok("2020-01-01T00:00:00+00:00".to_string())
+        );
+
+        // ISO week 1 of 2023 starts on monday 2023-01-02
+        let options = ParseOptions {
+            floor_exclusive: true,
+            ..Default::default()
+        };
+        let start = NaiveDate::from_ymd_opt(2023, 1, 2)
+            .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+            .expect("input date time invalid");
+        assert_eq!(
+            parse_str_with_options("now/Y", start, options).map(|res| res.to_rfc3339()),
+            Ok("2022-01-03T00:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn floor_month_edges_off_utc() {
+        // each date is in another month on the UTC clock
+        for (now, floored) in [
+            ("2023-12-31T23:59:59-05:00", "2023-12-01T00:00:00-05:00"),
+            ("2024-01-01T00:00:00+05:30", "2024-01-01T00:00:00+05:30"),
+            ("2024-02-29T23:59:59.999-05:00", "2024-02-01T00:00:00-05:00"),
+            ("2024-03-01T00:30:00+05:30", "2024-03-01T00:00:00+05:30"),
+            ("2023-02-28T20:00:00-05:00", "2023-02-01T00:00:00-05:00"),
+        ] {
+            let now = DateTime::parse_from_rfc3339(now).expect("input date time invalid");
+            assert_eq!(
+                parse_str_with_now("now/M", now).map(|res| res.to_rfc3339()),
+                Ok(floored.to_string()),
+                "{now}"
+            );
+        }
+    }
+
+    #[test]
+    fn floor_calendar_units_before_epoch() {
+        let now = NaiveDate::from_ymd_opt(2023, 8, 31)
+            .and_then(|date| date.and_hms_nano_opt(5, 40, 0, 123_456_789))
+            .expect("input date time invalid")
+            .and_utc();
+        let parse = |text| parse_str_with_now(text, now).map(|res| res.to_rfc3339());
+
+        assert_eq!(
+            parse("now-55y"),
+            Ok("1968-08-31T05:40:00.123456789+00:00".to_string())
+        );
+        assert_eq!(
+            parse("now-55y/y"),
+            Ok("1968-01-01T00:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            parse("now-55y/M"),
+            Ok("1968-08-01T00:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            parse("now-6M/y"),
+            Ok("2023-01-01T00:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            parse("now-2026y/y"),
+            Ok("-0003-01-01T00:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            parse("now-2026y/M"),
+            Ok("-0003-08-01T00:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn components_round_trip_around_leap_day() {
+        let offsets = [0, 5 * 3600 + 30 * 60, -8 * 3600]
+            .map(|seconds| FixedOffset::east_opt(seconds).expect("offset invalid"));
+        for (month, day) in [(1, 1), (2, 29), (3, 1), (12, 31)] {
+            for offset in offsets {
+                let time = offset
+                    .with_ymd_and_hms(2024, month, day, 12, 34, 56)
+                    .single()
+                    .expect("input date time invalid");
+                let components = TimeComponents::from(&time);
+                assert_eq!(
+                    components,
+                    TimeComponents {
+                        years: 2024,
+                        months: month.into(),
+                        days: day.into(),
+                        hours: 12,
+                        minutes: 34,
+                        seconds: 56,
+                        nanos: 0,
+                    },
+                    "{time}"
+                );
+
+                let field = |value| u32::try_from(value).expect("component out of range");
+                let local = i32::try_from(components.years)
+                    .ok()
+                    .and_then(|year| {
+                        NaiveDate::from_ymd_opt(
+                            year,
+                            field(components.months),
+                            field(components.days),
+                        )
+                    })
+                    .and_then(|date| {
+                        date.and_hms_nano_opt(
+                            field(components.hours),
+                            field(components.minutes),
+                            field(components.seconds),
+                            field(components.nanos),
+                        )
+                    })
+                    .expect("components invalid");
+                assert_eq!(offset.from_local_datetime(&local).single(), Some(time));
+            }
+        }
+    }
+
+    #[test]
+    fn calendar_units_keep_nanoseconds() {
+        let datetime = NaiveDate::from_ymd_opt(2023, 1, 31)
+            .and_then(|date| date.and_hms_nano_opt(5, 40, 0, 123_456_789))
+            .expect("input date time invalid")
+            .and_utc();
+
+        for text in ["now+1M", "now-1y", "now+1w", "now-1d", "now+1h-1m+1s"] {
+            let res = parse_str_with_now(text, datetime).expect("input date time invalid");
+            assert_eq!(res.nanosecond(), 123_456_789, "{text}");
+            assert_eq!(TimeComponents::from(&res).nanos, 123_456_789, "{text}");
+        }
+        assert_eq!(
+            parse_str_with_now("now+1M", datetime)
+                .map(|res| res.to_rfc3339_opts(SecondsFormat::Nanos, true)),
+            Ok("2023-02-28T05:40:00.123456789Z".to_string())
+        );
+    }
+
+    #[test]
+    fn resolve_placeholders() {
+        let now = DateTime::parse_from_rfc3339("2023-08-21T05:40:00+02:00")
+            .expect("input date time invalid");
+
+        assert_eq!(
+            resolve_in_text("from {now-1d/d} to {now} {{UTC+2}}", &now),
+            Ok("from 2023-08-20T00:00:00+02:00 to 2023-08-21T05:40:00+02:00 {UTC+2}".to_string())
+        );
+        assert_eq!(
+            resolve_in_text("no placeholders", &now),
+            Ok("no placeholders".to_string())
+        );
+        assert_eq!(
+            resolve_in_text_with("{now+1M} and {{{now}}}", &now, |time| time
+                .format("%F")
+                .to_string()),
+            Ok("2023-09-21 and {2023-08-21}".to_string())
+        );
+        // positions are in the whole template
+        assert_eq!(
+            resolve_in_text("at {now} and {now+1x}", &now),
+            Err(Error::UnexpectedCharacter(19, 'x'))
+        );
+        assert_eq!(
+            resolve_in_text("at {now+now}", &now),
+            Err(Error::MultipleNow(8))
+        );
+        assert_eq!(
+            resolve_in_text("at {now", &now),
+            Err(Error::UnmatchedBrace(3))
+        );
+        assert_eq!(
+            resolve_in_text("at now}", &now),
+            Err(Error::UnmatchedBrace(6))
+        );
+        assert_eq!(resolve_in_text("at {+1d}", &now), Err(Error::MissingNow));
+    }
+
+    #[test]
+    fn truncate_to_micros() {
+        let now = NaiveDate::from_ymd_opt(2023, 8, 21)
+            .and_then(|date| date.and_hms_nano_opt(5, 40, 0, 123_456_789))
+            .expect("input date time invalid")
+            .and_utc();
+        let options = ParseOptions {
+            truncate_to: Some(Unit::Microsecond),
+            ..Default::default()
+        };
+
+        for text in ["now", "now+1d", "now-1M+2s"] {
+            let res = parse_str_with_options(text, now, options).expect("input date time invalid");
+            assert_eq!(res.nanosecond(), 123_456_000, "{text}");
+        }
+        assert_eq!(
+            parse_str_with_options("now/d", now, options),
+            parse_str_with_now("now/d", now)
+        );
+        let options = ParseOptions {
+            truncate_to: Some(Unit::Hour),
+            ..options
+        };
+        assert_eq!(
+            parse_str_with_options("now+30m", now, options),
+            parse_str_with_now("now+30m/h", now)
+        );
+    }
+
+    #[test]
+    fn until_next() {
+        // a monday
+        let now = NaiveDate::from_ymd_opt(2023, 8, 21)
+            .and_then(|date| date.and_hms_opt(5, 40, 0))
+            .expect("input date time invalid")
+            .and_utc();
+
+        assert_eq!(
+            duration_until_next(&now, Unit::Hour),
+            Ok(TimeDelta::minutes(20))
+        );
+        assert_eq!(
+            duration_until_next(&now, Unit::Day),
+            Ok(TimeDelta::minutes(18 * 60 + 20))
+        );
+        assert_eq!(
+            duration_until_next(&now, Unit::Week),
+            Ok(TimeDelta::days(6) + TimeDelta::minutes(18 * 60 + 20))
+        );
+        // on the boundary it is the next one
+        let midnight = now - TimeDelta::minutes(5 * 60 + 40);
+        assert_eq!(
+            duration_until_next(&midnight, Unit::Hour),
+            Ok(TimeDelta::hours(1))
+        );
+        assert_eq!(
+            duration_until_next(&midnight, Unit::Day),
+            Ok(TimeDelta::days(1))
+        );
+        assert_eq!(
+            duration_until_next(&midnight, Unit::Week),
+            Ok(TimeDelta::weeks(1))
+        );
+
+        assert_eq!(
+            duration_until_next_hour(&now),
+            duration_until_next(&now, Unit::Hour)
+        );
+        assert_eq!(
+            duration_until_next_day(&now),
+            duration_until_next(&now, Unit::Day)
+        );
+        assert_eq!(
+            duration_until_next_week(&now),
+            duration_until_next(&now, Unit::Week)
+        );
+    }
+
+    #[test]
+    fn boundaries() {
+        let time = |month, day, hour| {
+            NaiveDate::from_ymd_opt(2023, month, day)
+                .and_then(|date| date.and_hms_opt(hour, 0, 0))
+                .expect("input date time invalid")
+                .and_utc()
+        };
+        let now = time(8, 21, 5);
+
+        let midnights = vec![time(8, 22, 0), time(8, 23, 0), time(8, 24, 0)];
+        assert_eq!(
+            boundaries_between(&now, &time(8, 24, 5), Unit::Day),
+            Ok(midnights.clone())
+        );
+        // descending when going back
+        let mut descending = midnights;
+        descending.reverse();
+        assert_eq!(
+            boundaries_between(&time(8, 24, 5), &now, Unit::Day),
+            Ok(descending)
+        );
+        // neither end is between them
+        assert_eq!(
+            boundaries_between(&time(8, 21, 0), &time(8, 23, 0), Unit::Day),
+            Ok(vec![time(8, 22, 0)])
+        );
+        assert_eq!(
+            boundaries_between(&now, &time(8, 21, 23), Unit::Day),
+            Ok(vec![])
+        );
+        assert_eq!(boundaries_between(&now, &now, Unit::Hour), Ok(vec![]));
+        assert_eq!(
+            boundaries_between(&now, &time(11, 2, 0), Unit::Month),
+            Ok(vec![time(9, 1, 0), time(10, 1, 0), time(11, 1, 0)])
+        );
+    }
+
+    #[test]
+    fn in_zone() {
+        let now = DateTime::parse_from_rfc3339("2023-08-21T20:00:00Z")
+            .expect("input date time invalid")
+            .to_utc();
+        let zone = FixedOffset::east_opt(5 * 3600 + 30 * 60).expect("offset invalid");
+        let parse = |text| {
+            parse_str_in_zone(text, now, &zone)
+                .map(|res| res.to_rfc3339_opts(SecondsFormat::Secs, true))
+        };
+
+        // already the next day in the zone
+        assert_eq!(parse("now/d"), Ok("2023-08-22T00:00:00+05:30".to_string()));
+        assert_eq!(parse("now/h"), Ok("2023-08-22T01:00:00+05:30".to_string()));
+        assert_eq!(
+            parse("now-1d/d"),
+            Ok("2023-08-21T00:00:00+05:30".to_string())
+        );
+        assert_eq!(parse("now"), Ok("2023-08-22T01:30:00+05:30".to_string()));
+        // in UTC it is still the day before
+        assert_eq!(
+            parse_str_with_now("now/d", now).map(|res| res.to_rfc3339()),
+            Ok("2023-08-21T00:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn signed_delta() {
+        let now = NaiveDate::from_ymd_opt(2024, 1, 15)
+            .and_then(|date| date.and_hms_opt(5, 40, 0))
+            .expect("input date time invalid")
+            .and_utc();
+
+        assert_eq!(
+            parse_str_signed_delta("now+2h", now),
+            Ok(TimeDelta::hours(2))
+        );
+        assert_eq!(
+            parse_str_signed_delta("now+1M", now),
+            Ok(TimeDelta::days(31))
+        );
+        assert_eq!(
+            parse_str_signed_delta("now+1M", now + TimeDelta::days(31)),
+            Ok(TimeDelta::days(29))
+        );
+        assert_eq!(
+            parse_str_signed_delta("now/d", now),
+            Ok(-TimeDelta::minutes(5 * 60 + 40))
+        );
+        assert_eq!(
+            parse_str_signed_delta("now+1x", now),
+            Err(Error::UnexpectedCharacter(5, 'x'))
+        );
+    }
+
+    #[test]
+    fn humanize_deltas() {
+        let now = DateTime::parse_from_rfc3339("2023-08-21T05:40:00+02:00")
+            .expect("input date time invalid");
+        let human = |text: &str| {
+            let result = parse_str_with_now(text, now).expect("input should parse");
+            humanize(&result, &now)
+        };
+
+        assert_eq!(human("now"), "now");
+        assert_eq!(human("now+1ms"), "now");
+        assert_eq!(human("now-1s"), "1 second ago");
+        assert_eq!(human("now+59s"), "in 59 seconds");
+        assert_eq!(human("now-90s"), "2 minutes ago");
+        assert_eq!(human("now+59m+40s"), "in 1 hour");
+        assert_eq!(human("now-3h"), "3 hours ago");
+        assert_eq!(human("now+2d"), "in 2 days");
+        assert_eq!(human("now-1w"), "7 days ago");
+        assert_eq!(human("now+2M"), "in 2 months");
+        assert_eq!(human("now-11M-20d"), "1 year ago");
+        assert_eq!(human("now+10y"), "in 10 years");
+    }
+
+    // The expression adding an ISO 8601 duration, with at most milliseconds
+    // and the years as 12 months each.
+    fn iso_expression(iso: &str) -> String {
+        let (sign, iso) = iso.strip_prefix('-').map_or(('+', iso), |iso| ('-', iso));
+        let mut expression = "now".to_string();
+        let mut value = String::new();
+        let mut months = 0;
+        let mut time = false;
+        for c in iso.chars().skip(1) {
+            if c.is_ascii_digit() || c == '.' {
+                value.push(c);
+                continue;
+            }
+            if matches!((c, time), ('Y' | 'M', false)) {
+                let count = value.parse::<u32>().expect("input duration invalid");
+                months += if c == 'Y' { count * 12 } else { count };
+                value.clear();
+                continue;
+            }
+            if months != 0 {
+                expression.push_str(&format!("{sign}{months}M"));
+                months = 0;
+            }
+            let unit = match c {
+                'T' => {
+                    time = true;
+                    continue;
+                }
+                'D' => "d",
+                'H' => "h",
+                'M' => "m",
+                _ => {
+                    let (seconds, fraction) = value.split_once('.').unwrap_or((&value, ""));
+                    let millis = format!("{fraction:0<3}");
+                    expression.push_str(&format!("{sign}{seconds}s{sign}{millis}ms"));
+                    value.clear();
+                    continue;
+                }
+            };
+            expression.push_str(&format!("{sign}{value}{unit}"));
+            value.clear();
+        }
+        if months != 0 {
+            expression.push_str(&format!("{sign}{months}M"));
+        }
+        expression
+    }
+
+    #[test]
+    fn iso8601_offset() {
+        let time =
+            |text: &str| DateTime::parse_from_rfc3339(text).expect("input date time invalid");
+        let base = time("2024-01-31T10:00:00+01:00");
+
+        for (target, iso) in [
+            ("2024-01-31T10:00:00+01:00", "PT0S"),
+            ("2024-03-01T12:30:00+01:00", "P1M1DT2H30M"),
+            ("2026-05-31T10:00:00+01:00", "P2Y4M"),
+            // clamped to the end of february, like `now+1M`
+            ("2024-02-29T10:00:01.25+01:00", "P1MT1.25S"),
+            ("2023-12-25T09:59:59.5+01:00", "-P1M6DT0.5S"),
+            ("2022-11-29T08:00:00+01:00", "-P1Y2M1DT2H"),
+        ] {
+            let target = time(target);
+            assert_eq!(to_iso8601_offset(&base, &target).as_deref(), Ok(iso));
+            assert_eq!(
+                parse_str_with_now(&iso_expression(iso), base),
+                Ok(target),
+                "{iso}"
+            );
+        }
+
+        // adding the year on its own clamps to the end of february first
+        let base = time("2024-02-29T12:00:00Z");
+        let target = time("2025-03-29T12:00:00Z");
+        assert_eq!(to_iso8601_offset(&base, &target).as_deref(), Ok("P1Y1M"));
+        assert_eq!(parse_str_with_now("now+13M", base), Ok(target));
+        assert_eq!(
+            parse_str_with_now("now+1y+1M", base),
+            Ok(time("2025-03-28T12:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn floor_business_hours() {
+        let options = ParseOptions {
+            business_hours: BusinessHours::new((9, 0), (17, 0)).ok(),
+            ..Default::default()
+        };
+        let floor = |now: &str, options| {
+            let now = DateTime::parse_from_rfc3339(now).expect("input date time invalid");
+            parse_str_with_options("now/bh", now, options)
+                .map(|res| res.to_rfc3339_opts(SecondsFormat::Secs, true))
+        };
+
+        for (now, expected) in [
+            // saturday afternoon, so friday's close
+            ("2023-08-26T15:00:00+02:00", "2023-08-25T17:00:00+02:00"),
+            // before opening on tuesday, so monday's close
+            ("2023-08-22T07:30:00+02:00", "2023-08-21T17:00:00+02:00"),
+            // before opening on monday, so friday's close
+            ("2023-08-21T08:59:59+02:00", "2023-08-18T17:00:00+02:00"),
+            ("2023-08-22T10:30:00+02:00", "2023-08-22T09:00:00+02:00"),
+            ("2023-08-22T09:00:00+02:00", "2023-08-22T09:00:00+02:00"),
+            ("2023-08-22T17:00:00+02:00", "2023-08-22T17:00:00+02:00"),
+            ("2023-08-22T23:00:00+02:00", "2023-08-22T17:00:00+02:00"),
+        ] {
+            assert_eq!(floor(now, options), Ok(expected.to_string()), "{now}");
+        }
+        assert_eq!(
+            floor("2023-08-22T10:30:00+02:00", ParseOptions::default()),
+            Err(Error::UnsupportedExpression(Expression::FloorBusinessHours))
+        );
+    }
+
+    #[test]
+    fn floor_quarter_and_half_hour() {
+        let now = DateTime::parse_from_rfc3339("2023-08-21T05:40:12.5+05:30")
+            .expect("input date time invalid");
+        let parse = |text, options| {
+            parse_str_with_options(text, now, options)
+                .map(|res| res.to_rfc3339_opts(SecondsFormat::Secs, true))
+        };
+        let options = ParseOptions::default();
+        assert_eq!(
+            parse("now/H", options),
+            Ok("2023-08-21T05:30:00+05:30".to_string())
+        );
+        assert_eq!(
+            parse("now/q", options),
+            Ok("2023-08-21T05:30:00+05:30".to_string())
+        );
+        assert_eq!(
+            parse("now+10m/q", options),
+            Ok("2023-08-21T05:45:00+05:30".to_string())
+        );
+        assert_eq!(
+            parse("now-11m/H", options),
+            Ok("2023-08-21T05:00:00+05:30".to_string())
+        );
+        let exclusive = ParseOptions {
+            floor_exclusive: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse("now/H/q", exclusive),
+            Ok("2023-08-21T05:15:00+05:30".to_string())
+        );
+        // `Q` is not the quarter hour
+        assert_eq!(
+            parse("now/Q", options),
+            Err(Error::UnexpectedCharacter(4, 'Q'))
+        );
+        assert_eq!(
+            parse("now+1q", options),
+            Err(Error::InvalidFormat(
+                5,
+                TokenType::Unit,
+                TokenType::QuarterHour
+            ))
+        );
+    }
+
+    #[test]
+    fn nth_weekday_of_month() {
+        let now = DateTime::parse_from_rfc3339("2023-08-21T05:40:00+02:00")
+            .expect("input date time invalid");
+        let parse = |text| {
+            parse_str_with_now(text, now).map(|res| res.to_rfc3339_opts(SecondsFormat::Secs, true))
+        };
+        assert_eq!(
+            parse("now+1M/3thu"),
+            Ok("2023-09-21T00:00:00+02:00".to_string())
+        );
+        // the first of september is a friday
+        assert_eq!(
+            parse("now+1M/1fri"),
+            Ok("2023-09-01T00:00:00+02:00".to_string())
+        );
+        assert_eq!(
+            parse("now+1M/5sat"),
+            Ok("2023-09-30T00:00:00+02:00".to_string())
+        );
+        assert_eq!(
+            parse("now+1M/5mon"),
+            Err(Error::NoSuchWeekday(5, Weekday::Monday))
+        );
+        assert_eq!(
+            parse("now/0mon"),
+            Err(Error::NoSuchWeekday(0, Weekday::Monday))
+        );
+        assert_eq!(
+            Error::NoSuchWeekday(5, Weekday::Monday).to_string(),
+            "the month has no monday number 5"
+        );
+    }
+
+    #[test]
+    fn floor_sub_second() {
+        let now = NaiveDate::from_ymd_opt(2023, 8, 21)
+            .and_then(|date| date.and_hms_nano_opt(5, 40, 0, 123_456_789))
+            .expect("input date time invalid")
+            .and_utc();
+        let nanos = |text: &str| parse_str_with_now(text, now).map(|res| res.nanosecond());
+
+        assert_eq!(nanos("now/ms"), Ok(123_000_000));
+        assert_eq!(nanos("now-1ms/ms"), Ok(122_000_000));
+        assert_eq!(nanos("now+877ms/s"), Ok(0));
+        assert_eq!(nanos("now/s"), Ok(0));
+        assert_eq!(
+            now.floor_micros().map(|res| res.nanosecond()),
+            Ok(123_456_000)
+        );
+    }
+
+    #[test]
+    fn end_of_units() {
+        let end = |now: &str, op: fn(DateTime<FixedOffset>) -> Result<DateTime<FixedOffset>>| {
+            let now = DateTime::parse_from_rfc3339(now).expect("input date time invalid");
+            op(now).map(|res| res.to_rfc3339_opts(SecondsFormat::Nanos, true))
+        };
+
+        for (now, expected) in [
+            (
+                "2024-02-10T05:40:00+05:30",
+                "2024-02-29T23:59:59.999999999+05:30",
+            ),
+            (
+                "2023-02-10T05:40:00+05:30",
+                "2023-02-28T23:59:59.999999999+05:30",
+            ),
+            (
+                "2024-02-01T00:00:00-05:00",
+                "2024-02-29T23:59:59.999999999-05:00",
+            ),
+            (
+                "2023-12-31T23:00:00-05:00",
+                "2023-12-31T23:59:59.999999999-05:00",
+            ),
+        ] {
+            assert_eq!(
+                end(now, RelativeTime::end_of_month),
+                Ok(expected.to_string())
+            );
+        }
+        // 2023-08-23 is a wednesday
+        let now = "2023-08-23T00:00:00Z";
+        assert_eq!(
+            end(now, RelativeTime::end_of_day),
+            Ok("2023-08-23T23:59:59.999999999Z".to_string())
+        );
+        assert_eq!(
+            end(now, RelativeTime::end_of_week),
+            Ok("2023-08-27T23:59:59.999999999Z".to_string())
+        );
+        assert_eq!(
+            end(now, RelativeTime::end_of_year),
+            Ok("2023-12-31T23:59:59.999999999Z".to_string())
+        );
+    }
+
+    #[test]
+    fn leap_day_years() {
+        let leap_day = NaiveDate::from_ymd_opt(2020, 2, 29)
+            .and_then(|date| date.and_hms_opt(5, 40, 0))
+            .expect("input date time invalid")
+            .and_utc();
+        for (text, expected) in [
+            ("now+1y", "2021-02-28T05:40:00+00:00"),
+            ("now+4y", "2024-02-29T05:40:00+00:00"),
+            ("now-1y", "2019-02-28T05:40:00+00:00"),
+            ("now+1y+3y", "2024-02-28T05:40:00+00:00"),
+        ] {
+            assert_eq!(
+                parse_str_with_now(text, leap_day).map(|res| res.to_rfc3339()),
+                Ok(expected.to_string()),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn rfc3339() {
+        assert_eq!(
+            apply_to_rfc3339("2023-08-21T05:40:00Z", "now+1d"),
+            Ok("2023-08-22T05:40:00Z".to_string())
+        );
+        assert_eq!(
+            apply_to_rfc3339("2023-08-21T05:40:00.25+05:30", "now-1M/d"),
+            Ok("2023-07-21T00:00:00+05:30".to_string())
+        );
+        assert_eq!(
+            apply_to_rfc3339("2023-08-21 05:40", "now+1d"),
+            Err(Error::InvalidBaseTimestamp)
+        );
+        assert_eq!(
+            apply_to_rfc3339("2023-08-21T05:40:00Z", "now+1x"),
+            Err(Error::UnexpectedCharacter(5, 'x'))
+        );
+    }
+
+    #[test]
+    fn distant_past() {
+        let min = DateTime::<Utc>::MIN_UTC;
+        for (text, index, expr) in [
+            ("now-1s", 3, Expression::Sub(1, Unit::Second)),
+            ("now-1M", 3, Expression::Sub(1, Unit::Month)),
+            ("now-1y", 3, Expression::Sub(1, Unit::Year)),
+            ("now-4294967295M", 3, Expression::Sub(u32::MAX, Unit::Month)),
+            ("now+1d-2d", 6, Expression::Sub(2, Unit::Day)),
+        ] {
+            assert_eq!(
+                parse_str_with_now(text, min),
+                Err(Error::OverflowAt(index, expr)),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            parse_str_with_now("now/y", min).map(|res| res.to_rfc3339()),
+            Ok("-262143-01-01T00:00:00+00:00".to_string())
+        );
+
+        let near = NaiveDate::from_ymd_opt(-262_140, 3, 31)
+            .and_then(|date| date.and_hms_opt(1, 0, 0))
+            .expect("input date time invalid")
+            .and_utc();
+        assert_eq!(
+            parse_str_with_now("now-1M", near).map(|res| res.to_rfc3339()),
+            Ok("-262140-02-29T01:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            parse_str_with_now("now-4y", near),
+            Err(Error::OverflowAt(3, Expression::Sub(4, Unit::Year)))
+        );
+    }
+
+    #[test]
+    fn floor_before_minimum() {
+        // the wall clock is in range, but the start of its year, month and
+        // day are before the first representable timestamp
+        let east = FixedOffset::east_opt(5 * 3600).expect("offset invalid");
+        let min = DateTime::<Utc>::MIN_UTC.with_timezone(&east);
+        for (text, expr) in [
+            ("now/y", Expression::Floor(Unit::Year)),
+            ("now/M", Expression::Floor(Unit::Month)),
+            ("now/w", Expression::Floor(Unit::Week)),
+            ("now/d", Expression::Floor(Unit::Day)),
+            ("now/Y", Expression::FloorIsoYear),
+        ] {
+            assert_eq!(
+                parse_str_with_now(text, min),
+                Err(Error::OverflowAt(3, expr)),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            parse_str_with_now("now/h", min).map(|res| res.to_rfc3339()),
+            Ok("-262143-01-01T05:00:00+05:00".to_string())
+        );
+
+        // a few days in, the year and month still start before the minimum
+        let near = (DateTime::<Utc>::MIN_UTC + TimeDelta::days(3)).with_timezone(&east);
+        for (text, expr) in [
+            ("now/y", Expression::Floor(Unit::Year)),
+            ("now/M", Expression::Floor(Unit::Month)),
+        ] {
+            assert_eq!(
+                parse_str_with_now(text, near),
+                Err(Error::OverflowAt(3, expr)),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            parse_str_with_now("now/d", near).map(|res| res.to_rfc3339()),
+            Ok("-262143-01-04T00:00:00+05:00".to_string())
+        );
+    }
+
+    #[test]
+    fn local_clock_out_of_range() {
+        // the wall clock is an hour before the first representable timestamp
+        let west = FixedOffset::west_opt(3600).expect("offset invalid");
+        let min = DateTime::<Utc>::MIN_UTC.with_timezone(&west);
+        for (text, expr) in [
+            ("now/y", Expression::Floor(Unit::Year)),
+            ("now/M", Expression::Floor(Unit::Month)),
+            ("now/w", Expression::Floor(Unit::Week)),
+            ("now/d", Expression::Floor(Unit::Day)),
+            ("now/h", Expression::Floor(Unit::Hour)),
+            ("now/Y", Expression::FloorIsoYear),
+        ] {
+            assert_eq!(
+                parse_str_with_now(text, min),
+                Err(Error::OverflowAt(3, expr)),
+                "{text}"
+            );
+        }
+        assert_eq!(RelativeTime::day_of_month(&min), None);
+
+        let east = FixedOffset::east_opt(3600).expect("offset invalid");
+        let max = DateTime::<Utc>::MAX_UTC.with_timezone(&east);
+        assert_eq!(
+            parse_str_with_now("now/d", max),
+            Err(Error::OverflowAt(3, Expression::Floor(Unit::Day)))
+        );
+        assert_eq!(
+            parse_str_with_now("now+1s", max),
+            Err(Error::OverflowAt(3, Expression::Add(1, Unit::Second)))
+        );
     }
 }
@@ -1,9 +1,21 @@
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveTime};
 
-use crate::RelativeTimeNow;
+use crate::{RelativeTimeNow, ZonedDate};
 
 impl RelativeTimeNow for DateTime<Local> {
     fn now() -> Self {
         Local::now()
     }
 }
+
+impl RelativeTimeNow for NaiveTime {
+    fn now() -> Self {
+        Local::now().time()
+    }
+}
+
+impl RelativeTimeNow for ZonedDate<Local> {
+    fn now() -> Self {
+        Self::new(Local::now().date_naive(), Local)
+    }
+}
@@ -0,0 +1,249 @@
+use chrono::{NaiveTime, TimeDelta, Timelike};
+
+use crate::{
+    HOURS_PER_DAY, MINUTES_PER_HOUR, RelativeTime, SECONDS_PER_MINUTE,
+    error::{Error, Result},
+    parser::Unit,
+};
+
+const SECONDS_PER_HOUR: u32 = MINUTES_PER_HOUR * SECONDS_PER_MINUTE;
+const SECONDS_PER_DAY: u32 = HOURS_PER_DAY * SECONDS_PER_HOUR;
+const NANOS_PER_SECOND: u32 = 1_000_000_000;
+const NANOS_PER_MILLI: u32 = 1_000_000;
+const NANOS_PER_MICRO: u32 = 1_000;
+
+// Offsets wrap around midnight, so only the part within a day matters. This
+// also keeps large values from overflowing.
+fn wrap(value: u32, seconds: u32) -> TimeDelta {
+    let days = SECONDS_PER_DAY / seconds;
+    TimeDelta::seconds(((value % days) * seconds).into())
+}
+
+/// A time of day, which wraps around midnight, e.g. `23:30` plus an hour is
+/// `00:30`.
+impl RelativeTime for NaiveTime {
+    fn add_years(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Year))
+    }
+
+    fn add_months(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Month))
+    }
+
+    fn add_weeks(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Week))
+    }
+
+    fn add_days(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Day))
+    }
+
+    fn add_hours(self, value: u32) -> Result<Self> {
+        Ok(self.overflowing_add_signed(wrap(value, SECONDS_PER_HOUR)).0)
+    }
+
+    fn add_minutes(self, value: u32) -> Result<Self> {
+        Ok(self
+            .overflowing_add_signed(wrap(value, SECONDS_PER_MINUTE))
+            .0)
+    }
+
+    fn add_seconds(self, value: u32) -> Result<Self> {
+        Ok(self.overflowing_add_signed(wrap(value, 1)).0)
+    }
+
+    // sub-second values stay below 50 days, so they need no `wrap` to fit
+    fn add_millis(self, value: u32) -> Result<Self> {
+        Ok(self
+            .overflowing_add_signed(TimeDelta::milliseconds(value.into()))
+            .0)
+    }
+
+    fn add_micros(self, value: u32) -> Result<Self> {
+        Ok(self
+            .overflowing_add_signed(TimeDelta::microseconds(value.into()))
+            .0)
+    }
+
+    fn add_nanos(self, value: u32) -> Result<Self> {
+        Ok(self
+            .overflowing_add_signed(TimeDelta::nanoseconds(value.into()))
+            .0)
+    }
+
+    fn sub_years(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Year))
+    }
+
+    fn sub_months(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Month))
+    }
+
+    fn sub_weeks(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Week))
+    }
+
+    fn sub_days(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Day))
+    }
+
+    fn sub_hours(self, value: u32) -> Result<Self> {
+        Ok(self.overflowing_sub_signed(wrap(value, SECONDS_PER_HOUR)).0)
+    }
+
+    fn sub_minutes(self, value: u32) -> Result<Self> {
+        Ok(self
+            .overflowing_sub_signed(wrap(value, SECONDS_PER_MINUTE))
+            .0)
+    }
+
+    fn sub_seconds(self, value: u32) -> Result<Self> {
+        Ok(self.overflowing_sub_signed(wrap(value, 1)).0)
+    }
+
+    fn sub_millis(self, value: u32) -> Result<Self> {
+        Ok(self
+            .overflowing_sub_signed(TimeDelta::milliseconds(value.into()))
+            .0)
+    }
+
+    fn sub_micros(self, value: u32) -> Result<Self> {
+        Ok(self
+            .overflowing_sub_signed(TimeDelta::microseconds(value.into()))
+            .0)
+    }
+
+    fn sub_nanos(self, value: u32) -> Result<Self> {
+        Ok(self
+            .overflowing_sub_signed(TimeDelta::nanoseconds(value.into()))
+            .0)
+    }
+
+    fn floor_years(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Year))
+    }
+
+    fn floor_months(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Month))
+    }
+
+    fn floor_weeks(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Week))
+    }
+
+    fn floor_days(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Day))
+    }
+
+    fn floor_hours(self) -> Result<Self> {
+        Self::from_hms_opt(self.hour(), 0, 0).ok_or(Error::InvalidTimestamp)
+    }
+
+    fn floor_minutes(self) -> Result<Self> {
+        Self::from_hms_opt(self.hour(), self.minute(), 0).ok_or(Error::InvalidTimestamp)
+    }
+
+    fn floor_seconds(self) -> Result<Self> {
+        // keeps a leap second, as that is the second the time is in
+        let nanos = self.nanosecond() / NANOS_PER_SECOND * NANOS_PER_SECOND;
+        self.with_nanosecond(nanos).ok_or(Error::InvalidTimestamp)
+    }
+
+    fn floor_millis(self) -> Result<Self> {
+        let nanos = self.nanosecond() / NANOS_PER_MILLI * NANOS_PER_MILLI;
+        self.with_nanosecond(nanos).ok_or(Error::InvalidTimestamp)
+    }
+
+    fn floor_micros(self) -> Result<Self> {
+        let nanos = self.nanosecond() / NANOS_PER_MICRO * NANOS_PER_MICRO;
+        self.with_nanosecond(nanos).ok_or(Error::InvalidTimestamp)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse_str_with_now, parser::Expression};
+
+    fn parse(input: &str, (hour, minute, second): (u32, u32, u32)) -> Result<NaiveTime> {
+        let now =
+            NaiveTime::from_hms_milli_opt(hour, minute, second, 250).expect("input time invalid");
+        parse_str_with_now(input, now)
+    }
+
+    fn time(hour: u32, minute: u32, second: u32, milli: u32) -> Result<NaiveTime> {
+        Ok(
+            NaiveTime::from_hms_milli_opt(hour, minute, second, milli)
+                .expect("output time invalid"),
+        )
+    }
+
+    #[test]
+    fn wraparound_at_midnight() {
+        assert_eq!(parse("now+1h", (23, 30, 0)), time(0, 30, 0, 250));
+        assert_eq!(parse("now-1h", (0, 30, 0)), time(23, 30, 0, 250));
+        assert_eq!(parse("now+45m", (23, 30, 0)), time(0, 15, 0, 250));
+        assert_eq!(parse("now-1s", (0, 0, 0)), time(23, 59, 59, 250));
+        assert_eq!(parse("now+25h", (23, 30, 0)), time(0, 30, 0, 250));
+        assert_eq!(
+            parse("now+24h-1440m+86400s", (12, 0, 0)),
+            time(12, 0, 0, 250)
+        );
+        assert_eq!(parse("now+750ms", (23, 59, 59)), time(0, 0, 0, 0));
+        assert_eq!(parse("now-500ms", (0, 0, 0)), time(23, 59, 59, 750));
+        let late = time(23, 59, 59, 750).expect("input time invalid");
+        assert_eq!(late.add_millis(500), time(0, 0, 0, 250));
+        assert_eq!(late.add_micros(250_000), time(0, 0, 0, 0));
+        assert_eq!(late.add_nanos(250_000_000), time(0, 0, 0, 0));
+        assert_eq!(
+            time(0, 0, 0, 0).and_then(|midnight| midnight.sub_micros(250_000)),
+            Ok(late)
+        );
+        assert_eq!(
+            parse("now+4294967295h", (0, 0, 0)),
+            time(15, 0, 0, 250),
+            "4294967295 hours is 178956970 days and 15 hours"
+        );
+    }
+
+    #[test]
+    fn floors() {
+        assert_eq!(parse("now/h", (23, 30, 15)), time(23, 0, 0, 0));
+        assert_eq!(parse("now/m", (23, 30, 15)), time(23, 30, 0, 0));
+        assert_eq!(parse("now/s", (23, 30, 15)), time(23, 30, 15, 0));
+        assert_eq!(parse("now/ms", (23, 30, 15)), time(23, 30, 15, 250));
+        let floored = time(23, 30, 15, 250)
+            .and_then(|now| now.add_nanos(1_500))
+            .and_then(RelativeTime::floor_micros);
+        assert_eq!(floored.map(|time| time.nanosecond()), Ok(250_001_000));
+        assert_eq!(parse("now+1h/h", (23, 30, 15)), time(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn unsupported() {
+        assert_eq!(
+            parse("now+1d", (12, 0, 0)),
+            Err(Error::UnsupportedUnit(Unit::Day))
+        );
+        assert_eq!(
+            parse("now-1w", (12, 0, 0)),
+            Err(Error::UnsupportedUnit(Unit::Week))
+        );
+        assert_eq!(
+            parse("now+1M", (12, 0, 0)),
+            Err(Error::UnsupportedUnit(Unit::Month))
+        );
+        assert_eq!(
+            parse("now-1y", (12, 0, 0)),
+            Err(Error::UnsupportedUnit(Unit::Year))
+        );
+        assert_eq!(
+            parse("now/d", (12, 0, 0)),
+            Err(Error::UnsupportedUnit(Unit::Day))
+        );
+        assert_eq!(
+            parse("epoch+1h", (12, 0, 0)),
+            Err(Error::UnsupportedExpression(Expression::Epoch(0)))
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! Named IANA time zones, through `chrono-tz`. A `DateTime<Tz>` floors on the
+//! wall clock of its zone, following its daylight saving time rules, like any
+//! other [`chrono::TimeZone`]. [`Tz`] is re-exported so its version matches.
+
+pub use chrono_tz::Tz;
+
+#[cfg(test)]
+mod test {
+    use chrono::{DateTime, SecondsFormat};
+
+    use super::*;
+    use crate::{
+        Error, ParseOptions, TimeComponents, boundaries_between, parse_str_with_now,
+        parse_str_with_options, parser::Unit,
+    };
+
+    fn parse(text: &str, now: &str) -> crate::Result<String> {
+        let now = DateTime::parse_from_rfc3339(now)
+            .expect("input date time invalid")
+            .with_timezone(&Tz::America__New_York);
+        parse_str_with_now(text, now).map(|res| res.to_rfc3339_opts(SecondsFormat::Secs, false))
+    }
+
+    #[test]
+    fn spring_forward() {
+        // 2024-03-10 has 23 hours, 02:00 EST is followed by 03:00 EDT
+        let now = "2024-03-10T20:00:00-04:00";
+        assert_eq!(
+            parse("now/d", now),
+            Ok("2024-03-10T00:00:00-05:00".to_string())
+        );
+        assert_eq!(
+            parse("now-1d/d", now),
+            Ok("2024-03-09T00:00:00-05:00".to_string())
+        );
+        assert_eq!(
+            parse("now+1d/d", now),
+            Ok("2024-03-11T00:00:00-04:00".to_string())
+        );
+        assert_eq!(
+            parse("now/w", now),
+            Ok("2024-03-04T00:00:00-05:00".to_string())
+        );
+        assert_eq!(
+            parse("now/M", now),
+            Ok("2024-03-01T00:00:00-05:00".to_string())
+        );
+        assert_eq!(
+            parse("now/h", "2024-03-10T03:30:00-04:00"),
+            Ok("2024-03-10T03:00:00-04:00".to_string())
+        );
+    }
+
+    #[test]
+    fn fall_back() {
+        // 01:00 to 02:00 occurs twice on 2024-11-03, first in EDT then in EST
+        assert_eq!(
+            parse("now/d", "2024-11-03T23:00:00-05:00"),
+            Ok("2024-11-03T00:00:00-04:00".to_string())
+        );
+        assert_eq!(
+            parse("now/h", "2024-11-03T01:30:00-04:00"),
+            Ok("2024-11-03T01:00:00-04:00".to_string())
+        );
+        assert_eq!(
+            parse("now/h", "2024-11-03T01:30:00-05:00"),
+            Ok("2024-11-03T01:00:00-05:00".to_string())
+        );
+        assert_eq!(
+            parse("now/m", "2024-11-03T01:30:30-05:00"),
+            Ok("2024-11-03T01:30:00-05:00".to_string())
+        );
+    }
+
+    #[test]
+    fn calendar_units_keep_wall_clock() {
+        for (text, now, expected) in [
+            // a month with the clocks moved forward in between is shorter
+            (
+                "now+1M",
+                "2024-02-10T12:00:00-05:00",
+                "2024-03-10T12:00:00-04:00",
+            ),
+            (
+                "now-1M",
+                "2024-04-09T12:00:00-04:00",
+                "2024-03-09T12:00:00-05:00",
+            ),
+            (
+                "now+1y",
+                "2023-07-01T09:00:00-04:00",
+                "2024-07-01T09:00:00-04:00",
+            ),
+            (
+                "now+1d",
+                "2024-11-02T12:00:00-04:00",
+                "2024-11-03T12:00:00-05:00",
+            ),
+            (
+                "now+1w",
+                "2024-03-05T08:00:00-05:00",
+                "2024-03-12T08:00:00-04:00",
+            ),
+            // hours are exact, so they do not
+            (
+                "now+24h",
+                "2024-11-02T12:00:00-04:00",
+                "2024-11-03T11:00:00-05:00",
+            ),
+            // a wall clock that occurs twice takes the first
+            (
+                "now+1M",
+                "2024-10-03T01:30:00-04:00",
+                "2024-11-03T01:30:00-04:00",
+            ),
+            (
+                "now-1d",
+                "2024-11-04T01:30:00-05:00",
+                "2024-11-03T01:30:00-04:00",
+            ),
+            // a skipped wall clock moves forward by the hour that was skipped
+            (
+                "now+1M",
+                "2024-02-10T02:30:00-05:00",
+                "2024-03-10T03:30:00-04:00",
+            ),
+            (
+                "now+1d",
+                "2024-03-09T02:30:00-05:00",
+                "2024-03-10T03:30:00-04:00",
+            ),
+        ] {
+            assert_eq!(
+                parse(text, now),
+                Ok(expected.to_string()),
+                "{text} from {now}"
+            );
+        }
+    }
+
+    #[test]
+    fn reject_ambiguous_local_time() {
+        let options = ParseOptions {
+            reject_ambiguous_local_time: true,
+            ..Default::default()
+        };
+        let parse = |text, now| {
+            let now = DateTime::parse_from_rfc3339(now)
+                .expect("input date time invalid")
+                .with_timezone(&Tz::America__New_York);
+            parse_str_with_options(text, now, options)
+                .map(|res| res.to_rfc3339_opts(SecondsFormat::Secs, false))
+        };
+        // 01:00 to 02:00 occurs twice on 2024-11-03
+        let repeated = |hours, minutes| TimeComponents {
+            years: 2024,
+            months: 11,
+            days: 3,
+            hours,
+            minutes,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse("now/h", "2024-11-03T01:30:00-05:00"),
+            Err(Error::AmbiguousLocalTime(repeated(1, 0)))
+        );
+        assert_eq!(
+            parse("now+1d", "2024-11-02T01:30:00-04:00"),
+            Err(Error::AmbiguousLocalTime(repeated(1, 30)))
+        );
+        assert_eq!(
+            parse("now-1M", "2024-12-03T01:30:00-05:00"),
+            Err(Error::AmbiguousLocalTime(repeated(1, 30)))
+        );
+        assert_eq!(
+            Error::AmbiguousLocalTime(repeated(1, 30)).to_string(),
+            "the local time 2024-11-03 01:30:00 occurs twice"
+        );
+        // exact units and floors away from the repeated hour are fine
+        assert_eq!(
+            parse("now+1h", "2024-11-03T00:30:00-04:00"),
+            Ok("2024-11-03T01:30:00-04:00".to_string())
+        );
+        assert_eq!(
+            parse("now/d", "2024-11-03T01:30:00-05:00"),
+            Ok("2024-11-03T00:00:00-04:00".to_string())
+        );
+        assert_eq!(
+            parse("now+1d/d", "2024-11-02T01:30:00-04:00"),
+            Err(Error::AmbiguousLocalTime(repeated(1, 30)))
+        );
+    }
+
+    #[test]
+    fn skipped_wall_clock() {
+        // the clocks of Sao Paulo skipped midnight on 2018-11-04, so the day
+        // starts at 01:00
+        let now = DateTime::parse_from_rfc3339("2018-11-04T12:00:00-02:00")
+            .expect("input date time invalid")
+            .with_timezone(&Tz::America__Sao_Paulo);
+        assert_eq!(
+            parse_str_with_now("now/d", now).map(|res| res.to_rfc3339()),
+            Ok("2018-11-04T01:00:00-02:00".to_string())
+        );
+        assert_eq!(
+            parse_str_with_now("now+1d/d-1d/d", now).map(|res| res.to_rfc3339()),
+            Ok("2018-11-04T01:00:00-02:00".to_string())
+        );
+        assert_eq!(
+            parse_str_with_now("now/h", now).map(|res| res.to_rfc3339()),
+            Ok("2018-11-04T12:00:00-02:00".to_string())
+        );
+    }
+
+    #[test]
+    fn boundaries() {
+        let time = |text| {
+            DateTime::parse_from_rfc3339(text)
+                .expect("input date time invalid")
+                .with_timezone(&Tz::America__Sao_Paulo)
+        };
+        let rfc3339 = |times: Vec<DateTime<Tz>>| {
+            times
+                .iter()
+                .map(|time| time.to_rfc3339())
+                .collect::<Vec<_>>()
+        };
+        // the day the clocks skipped midnight starts at 01:00
+        assert_eq!(
+            boundaries_between(
+                &time("2018-11-02T12:00:00-03:00"),
+                &time("2018-11-06T12:00:00-02:00"),
+                Unit::Day
+            )
+            .map(rfc3339),
+            Ok(vec![
+                "2018-11-03T00:00:00-03:00".to_string(),
+                "2018-11-04T01:00:00-02:00".to_string(),
+                "2018-11-05T00:00:00-02:00".to_string(),
+                "2018-11-06T00:00:00-02:00".to_string(),
+            ])
+        );
+    }
+}
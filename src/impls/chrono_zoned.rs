@@ -0,0 +1,268 @@
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveTime, TimeZone};
+
+use crate::{
+    RelativeTime,
+    error::{Error, Result},
+    parser::{Unit, Weekday},
+};
+
+/// A calendar date in a time zone, for all-day events. Only days and larger
+/// units are supported, the zone only matters once the date is resolved to an
+/// instant with [`ZonedDate::at_midnight`].
+#[derive(Debug, Clone, Copy)]
+pub struct ZonedDate<Tz: TimeZone> {
+    pub date: NaiveDate,
+    pub timezone: Tz,
+}
+
+impl<Tz: TimeZone> ZonedDate<Tz> {
+    pub fn new(date: NaiveDate, timezone: Tz) -> Self {
+        Self { date, timezone }
+    }
+
+    /// The start of the day in the time zone, the earliest one when midnight
+    /// occurs twice. Results in [`Error::InvalidTimestamp`] when the clocks
+    /// skip midnight.
+    pub fn at_midnight(&self) -> Result<DateTime<Tz>> {
+        self.timezone
+            .from_local_datetime(&self.date.and_time(NaiveTime::MIN))
+            .earliest()
+            .ok_or(Error::InvalidTimestamp)
+    }
+
+    fn with_date(self, date: Option<NaiveDate>) -> Result<Self> {
+        let date = date.ok_or(Error::InvalidTimestamp)?;
+        Ok(Self { date, ..self })
+    }
+}
+
+/// Only the dates are compared, as not every time zone can be compared.
+impl<Tz: TimeZone> PartialEq for ZonedDate<Tz> {
+    fn eq(&self, other: &Self) -> bool {
+        self.date == other.date
+    }
+}
+
+impl<Tz: TimeZone> RelativeTime for ZonedDate<Tz> {
+    fn add_months(self, value: u32) -> Result<Self> {
+        let date = self.date.checked_add_months(Months::new(value));
+        self.with_date(date)
+    }
+
+    fn add_days(self, value: u32) -> Result<Self> {
+        let date = self.date.checked_add_days(Days::new(value.into()));
+        self.with_date(date)
+    }
+
+    fn add_hours(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Hour))
+    }
+
+    fn add_minutes(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Minute))
+    }
+
+    fn add_seconds(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Second))
+    }
+
+    fn sub_months(self, value: u32) -> Result<Self> {
+        let date = self.date.checked_sub_months(Months::new(value));
+        self.with_date(date)
+    }
+
+    fn sub_days(self, value: u32) -> Result<Self> {
+        let date = self.date.checked_sub_days(Days::new(value.into()));
+        self.with_date(date)
+    }
+
+    fn sub_hours(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Hour))
+    }
+
+    fn sub_minutes(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Minute))
+    }
+
+    fn sub_seconds(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Second))
+    }
+
+    fn floor_years(self) -> Result<Self> {
+        let date = NaiveDate::from_ymd_opt(self.date.year(), 1, 1);
+        self.with_date(date)
+    }
+
+    fn floor_months(self) -> Result<Self> {
+        let date = NaiveDate::from_ymd_opt(self.date.year(), self.date.month(), 1);
+        self.with_date(date)
+    }
+
+    fn floor_weeks(self) -> Result<Self> {
+        let days = self.date.weekday().num_days_from_monday();
+        self.sub_days(days)
+    }
+
+    fn floor_iso_years(self) -> Result<Self> {
+        let year = self.date.iso_week().year();
+        let date = NaiveDate::from_isoywd_opt(year, 1, chrono::Weekday::Mon);
+        self.with_date(date)
+    }
+
+    fn floor_days(self) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn floor_hours(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Hour))
+    }
+
+    fn floor_minutes(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Minute))
+    }
+
+    fn floor_seconds(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Second))
+    }
+
+    fn day_of_week(&self) -> Option<Weekday> {
+        Some(self.date.weekday().into())
+    }
+
+    fn day_of_month(&self) -> Option<u32> {
+        Some(self.date.day())
+    }
+
+    fn epoch(&self) -> Option<Self> {
+        let date = DateTime::UNIX_EPOCH.date_naive();
+        Some(Self {
+            date,
+            ..self.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{FixedOffset, MappedLocalTime, NaiveDateTime, SecondsFormat, TimeDelta, Utc};
+
+    use super::*;
+    use crate::parse_str_with_now;
+
+    // Like São Paulo in 2018, where the clocks moved from midnight to one
+    // o'clock on 2018-11-04.
+    #[derive(Debug, Clone, Copy)]
+    struct SaoPaulo;
+
+    impl SaoPaulo {
+        fn change() -> NaiveDateTime {
+            NaiveDate::from_ymd_opt(2018, 11, 4)
+                .map(|date| date.and_time(NaiveTime::MIN))
+                .expect("change date invalid")
+        }
+
+        fn offset(hours: i32) -> FixedOffset {
+            FixedOffset::west_opt(hours * 3600).expect("offset invalid")
+        }
+    }
+
+    impl TimeZone for SaoPaulo {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> Self {
+            Self
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> MappedLocalTime<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_local_datetime(
+            &self,
+            local: &NaiveDateTime,
+        ) -> MappedLocalTime<FixedOffset> {
+            if *local < Self::change() {
+                MappedLocalTime::Single(Self::offset(3))
+            } else if *local < Self::change() + TimeDelta::hours(1) {
+                MappedLocalTime::None
+            } else {
+                MappedLocalTime::Single(Self::offset(2))
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            if *utc < Self::change() + TimeDelta::hours(3) {
+                Self::offset(3)
+            } else {
+                Self::offset(2)
+            }
+        }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).expect("input date invalid")
+    }
+
+    fn midnight(text: &str, now: NaiveDate) -> Result<String> {
+        parse_str_with_now(text, ZonedDate::new(now, SaoPaulo))?
+            .at_midnight()
+            .map(|res| res.to_rfc3339_opts(SecondsFormat::Secs, true))
+    }
+
+    #[test]
+    fn all_day() {
+        let now = ZonedDate::new(date(2023, 8, 23), Utc);
+        assert_eq!(
+            parse_str_with_now("now+1d", now).map(|res| res.date),
+            Ok(date(2023, 8, 24))
+        );
+        assert_eq!(
+            parse_str_with_now("now-1M/w", now).map(|res| res.date),
+            Ok(date(2023, 7, 17))
+        );
+        assert_eq!(
+            parse_str_with_now("now+1y/y", now).map(|res| res.date),
+            Ok(date(2024, 1, 1))
+        );
+        assert_eq!(
+            parse_str_with_now("now next sun", now).map(|res| res.date),
+            Ok(date(2023, 8, 27))
+        );
+        assert_eq!(
+            parse_str_with_now("now+1h", now),
+            Err(Error::UnsupportedUnit(Unit::Hour))
+        );
+    }
+
+    #[test]
+    fn end_of_units_need_nanos() {
+        let now = ZonedDate::new(date(2023, 8, 23), Utc);
+        assert_eq!(now.end_of_month(), Err(Error::UnsupportedNanos));
+    }
+
+    #[test]
+    fn spring_forward() {
+        assert_eq!(
+            midnight("now", date(2018, 11, 3)),
+            Ok("2018-11-03T00:00:00-03:00".to_string())
+        );
+        assert_eq!(
+            midnight("now+1d", date(2018, 11, 3)),
+            Err(Error::InvalidTimestamp)
+        );
+        assert_eq!(
+            midnight("now+2d", date(2018, 11, 3)),
+            Ok("2018-11-05T00:00:00-02:00".to_string())
+        );
+        // the day itself can still be used for arithmetic
+        assert_eq!(
+            parse_str_with_now("now+1d+1w", ZonedDate::new(date(2018, 11, 3), SaoPaulo))
+                .map(|res| res.date),
+            Ok(date(2018, 11, 11))
+        );
+    }
+}
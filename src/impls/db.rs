@@ -0,0 +1,291 @@
+//! The chrono types `sqlx` decodes date and time columns into, besides
+//! `DateTime<Utc>` for `TIMESTAMPTZ` and `NaiveTime` for `TIME`, which are
+//! supported by the chrono backends already. Columns without a time zone are
+//! taken to be in UTC, and `now` is the current time in UTC. Both are limited
+//! to the range of chrono, roughly the years -262143 to 262142.
+
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+
+use crate::{RelativeTime, RelativeTimeNow, ZonedDate, error::Result, parser::Weekday};
+
+// Resolves a `TIMESTAMP` column through the UTC backend of chrono.
+fn utc(
+    time: NaiveDateTime,
+    op: impl FnOnce(chrono::DateTime<Utc>) -> Result<chrono::DateTime<Utc>>,
+) -> Result<NaiveDateTime> {
+    op(time.and_utc()).map(|time| time.naive_utc())
+}
+
+// Resolves a `DATE` column as a date in UTC.
+fn date(
+    date: NaiveDate,
+    op: impl FnOnce(ZonedDate<Utc>) -> Result<ZonedDate<Utc>>,
+) -> Result<NaiveDate> {
+    op(ZonedDate::new(date, Utc)).map(|time| time.date)
+}
+
+impl RelativeTime for NaiveDateTime {
+    fn add_months(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.add_months(value))
+    }
+
+    fn add_days(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.add_days(value))
+    }
+
+    fn add_seconds(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.add_seconds(value))
+    }
+
+    fn sub_months(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.sub_months(value))
+    }
+
+    fn sub_days(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.sub_days(value))
+    }
+
+    fn sub_seconds(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.sub_seconds(value))
+    }
+
+    fn sub_nanos(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.sub_nanos(value))
+    }
+
+    fn add_nanos(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.add_nanos(value))
+    }
+
+    fn add_millis(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.add_millis(value))
+    }
+
+    fn sub_millis(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.sub_millis(value))
+    }
+
+    fn add_micros(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.add_micros(value))
+    }
+
+    fn sub_micros(self, value: u32) -> Result<Self> {
+        utc(self, |time| time.sub_micros(value))
+    }
+
+    fn floor_years(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_years)
+    }
+
+    fn floor_months(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_months)
+    }
+
+    fn floor_weeks(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_weeks)
+    }
+
+    fn floor_days(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_days)
+    }
+
+    fn floor_hours(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_hours)
+    }
+
+    fn floor_minutes(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_minutes)
+    }
+
+    fn floor_seconds(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_seconds)
+    }
+
+    fn floor_millis(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_millis)
+    }
+
+    fn floor_micros(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_micros)
+    }
+
+    fn floor_iso_years(self) -> Result<Self> {
+        utc(self, RelativeTime::floor_iso_years)
+    }
+
+    fn day_of_week(&self) -> Option<Weekday> {
+        self.and_utc().day_of_week()
+    }
+
+    fn day_of_month(&self) -> Option<u32> {
+        self.and_utc().day_of_month()
+    }
+
+    fn epoch(&self) -> Option<Self> {
+        Some(chrono::DateTime::UNIX_EPOCH.naive_utc())
+    }
+}
+
+impl RelativeTime for NaiveDate {
+    fn add_months(self, value: u32) -> Result<Self> {
+        date(self, |time| time.add_months(value))
+    }
+
+    fn add_days(self, value: u32) -> Result<Self> {
+        date(self, |time| time.add_days(value))
+    }
+
+    fn add_hours(self, value: u32) -> Result<Self> {
+        date(self, |time| time.add_hours(value))
+    }
+
+    fn add_minutes(self, value: u32) -> Result<Self> {
+        date(self, |time| time.add_minutes(value))
+    }
+
+    fn add_seconds(self, value: u32) -> Result<Self> {
+        date(self, |time| time.add_seconds(value))
+    }
+
+    fn sub_months(self, value: u32) -> Result<Self> {
+        date(self, |time| time.sub_months(value))
+    }
+
+    fn sub_days(self, value: u32) -> Result<Self> {
+        date(self, |time| time.sub_days(value))
+    }
+
+    fn sub_hours(self, value: u32) -> Result<Self> {
+        date(self, |time| time.sub_hours(value))
+    }
+
+    fn sub_minutes(self, value: u32) -> Result<Self> {
+        date(self, |time| time.sub_minutes(value))
+    }
+
+    fn sub_seconds(self, value: u32) -> Result<Self> {
+        date(self, |time| time.sub_seconds(value))
+    }
+
+    fn floor_years(self) -> Result<Self> {
+        date(self, RelativeTime::floor_years)
+    }
+
+    fn floor_months(self) -> Result<Self> {
+        date(self, RelativeTime::floor_months)
+    }
+
+    fn floor_weeks(self) -> Result<Self> {
+        date(self, RelativeTime::floor_weeks)
+    }
+
+    fn floor_days(self) -> Result<Self> {
+        date(self, RelativeTime::floor_days)
+    }
+
+    fn floor_hours(self) -> Result<Self> {
+        date(self, RelativeTime::floor_hours)
+    }
+
+    fn floor_minutes(self) -> Result<Self> {
+        date(self, RelativeTime::floor_minutes)
+    }
+
+    fn floor_seconds(self) -> Result<Self> {
+        date(self, RelativeTime::floor_seconds)
+    }
+
+    fn floor_iso_years(self) -> Result<Self> {
+        date(self, RelativeTime::floor_iso_years)
+    }
+
+    fn day_of_week(&self) -> Option<Weekday> {
+        ZonedDate::new(*self, Utc).day_of_week()
+    }
+
+    fn day_of_month(&self) -> Option<u32> {
+        ZonedDate::new(*self, Utc).day_of_month()
+    }
+
+    fn epoch(&self) -> Option<Self> {
+        Some(chrono::DateTime::UNIX_EPOCH.date_naive())
+    }
+}
+
+impl RelativeTimeNow for NaiveDateTime {
+    fn now() -> Self {
+        Utc::now().naive_utc()
+    }
+}
+
+impl RelativeTimeNow for NaiveDate {
+    fn now() -> Self {
+        Utc::now().date_naive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{DateTime, NaiveTime};
+
+    use super::*;
+    use crate::{Error, parse_str, parse_str_with_now, parser::Unit};
+
+    fn timestamp() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 8, 21)
+            .and_then(|date| date.and_hms_opt(5, 40, 0))
+            .expect("input date time invalid")
+    }
+
+    #[test]
+    fn timestamp_matches_utc() {
+        let now = timestamp();
+        for text in [
+            "now+1d",
+            "now-1M/d",
+            "now/w+2h",
+            "now/Y",
+            "now next fri",
+            "epoch+1d",
+        ] {
+            assert_eq!(
+                parse_str_with_now(text, now),
+                parse_str_with_now(text, now.and_utc()).map(|res| res.naive_utc()),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn date_matches_utc() {
+        let now = timestamp().date();
+        for text in [
+            "now+1d",
+            "now-1M/M",
+            "now/w",
+            "now/Y",
+            "now next fri",
+            "epoch+1d",
+        ] {
+            assert_eq!(
+                parse_str_with_now(text, now),
+                parse_str_with_now(text, now.and_time(NaiveTime::MIN).and_utc())
+                    .map(|res| res.date_naive()),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            parse_str_with_now("now+1h", now),
+            Err(Error::UnsupportedUnit(Unit::Hour))
+        );
+    }
+
+    #[test]
+    fn now() {
+        let before = Utc::now();
+        assert!(parse_str::<DateTime<Utc>>("now").is_ok_and(|now| now >= before));
+        assert!(parse_str::<NaiveDateTime>("now-1d").is_ok_and(|now| now < before.naive_utc()));
+        assert!(parse_str::<NaiveDate>("now+1d").is_ok_and(|now| now > before.date_naive()));
+        assert!(parse_str::<NaiveTime>("now/h").is_ok());
+    }
+}
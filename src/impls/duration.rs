@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use crate::{
+    RelativeTime,
+    error::{Error, Result},
+    parser::Unit,
+};
+
+/// A duration since some start, of up to `u64::MAX` seconds. Without a
+/// calendar, years and months are not supported.
+impl RelativeTime for Duration {
+    fn add_years(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Year))
+    }
+
+    fn add_months(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Month))
+    }
+
+    fn add_seconds(self, value: u32) -> Result<Self> {
+        self.checked_add(Self::from_secs(value.into()))
+            .ok_or(Error::InvalidDelta)
+    }
+
+    fn sub_years(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Year))
+    }
+
+    fn sub_months(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Month))
+    }
+
+    fn sub_seconds(self, value: u32) -> Result<Self> {
+        self.checked_sub(Self::from_secs(value.into()))
+            .ok_or(Error::NegativeDuration)
+    }
+
+    fn floor_years(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Year))
+    }
+
+    fn floor_months(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Month))
+    }
+
+    fn floor_weeks(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Week))
+    }
+
+    fn floor_days(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Day))
+    }
+
+    fn floor_hours(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Hour))
+    }
+
+    fn floor_minutes(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Minute))
+    }
+
+    fn floor_seconds(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Second))
+    }
+
+    fn epoch(&self) -> Option<Self> {
+        Some(Self::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_str_with_now;
+
+    fn parse(input: &str) -> Result<Duration> {
+        parse_str_with_now(input, Duration::ZERO)
+    }
+
+    #[test]
+    fn now() {
+        assert_eq!(parse("now"), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn epoch() {
+        let now = Duration::from_secs(1 << 20);
+        assert_eq!(parse_str_with_now("epoch", now), Ok(Duration::ZERO));
+        assert_eq!(
+            parse_str_with_now("epoch+90s", now),
+            Ok(Duration::from_secs(90))
+        );
+    }
+
+    #[test]
+    fn add() {
+        assert_eq!(parse("now+90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse("now+1d-2h"), Ok(Duration::from_secs(22 * 3600)));
+        assert_eq!(parse("now+1w"), Ok(Duration::from_secs(7 * 24 * 3600)));
+    }
+
+    #[test]
+    fn negative() {
+        assert_eq!(parse("now-1s"), Err(Error::NegativeDuration));
+        assert_eq!(parse("now+1m-2m"), Err(Error::NegativeDuration));
+    }
+
+    #[test]
+    fn unsupported() {
+        assert_eq!(parse("now+1M"), Err(Error::UnsupportedUnit(Unit::Month)));
+        assert_eq!(parse("now-1y"), Err(Error::UnsupportedUnit(Unit::Year)));
+        assert_eq!(parse("now/d"), Err(Error::UnsupportedUnit(Unit::Day)));
+    }
+}
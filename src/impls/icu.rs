@@ -0,0 +1,270 @@
+use icu_calendar::{Date, DateDuration, Gregorian, types::IsoWeekday};
+
+use crate::{
+    RelativeTime,
+    error::{Error, Result},
+    parser::{Unit, Weekday},
+};
+
+// ICU adds the day of the month to the delta in days without checking for
+// overflow, so keep some room for it.
+const MAX_DAYS_PER_MONTH: i32 = 31;
+const MIN_DAYS_PER_YEAR: i64 = 365;
+
+fn delta(value: u32) -> Result<i32> {
+    i32::try_from(value)
+        .ok()
+        .filter(|value| value.checked_add(MAX_DAYS_PER_MONTH).is_some())
+        .ok_or(Error::InvalidDelta)
+}
+
+// Dates are moved with the calendar arithmetic of ICU, which does not check
+// whether the year still fits.
+fn offset(
+    mut date: Date<Gregorian>,
+    years: i32,
+    months: i32,
+    days: i32,
+) -> Result<Date<Gregorian>> {
+    let span = i64::from(years) + i64::from(months) / 12 + i64::from(days) / MIN_DAYS_PER_YEAR;
+    let year = i64::from(date.to_iso().year().number) + span;
+    if year.abs() >= i64::from(i32::MAX) - 1 {
+        return Err(Error::InvalidTimestamp);
+    }
+    date.add(DateDuration::new(years, months, 0, days));
+    Ok(date)
+}
+
+// Moves by years and months from the first of the month, which ICU never
+// spills over, and then clamps the day to the end of the resulting month.
+fn offset_months(date: Date<Gregorian>, years: i32, months: i32) -> Result<Date<Gregorian>> {
+    let day = date.day_of_month().0;
+    let start = from_iso(date.to_iso().year().number, month(date)?, 1)?;
+    let moved = offset(start, years, months, 0)?;
+    let day = u8::try_from(day.min(moved.days_in_month().into()))
+        .map_err(|_err| Error::InvalidTimestamp)?;
+    from_iso(moved.to_iso().year().number, month(moved)?, day)
+}
+
+fn from_iso(year: i32, month: u8, day: u8) -> Result<Date<Gregorian>> {
+    Date::try_new_gregorian_date(year, month, day).map_err(|_err| Error::InvalidTimestamp)
+}
+
+fn month(date: Date<Gregorian>) -> Result<u8> {
+    u8::try_from(date.month().ordinal).map_err(|_err| Error::InvalidTimestamp)
+}
+
+/// Dates in the gregorian calendar of ICU4X. Years and months clamp to the end
+/// of the month like the other backends, while spilling over into the next
+/// month matches the arithmetic of ICU. Years have to fit in an `i32`.
+impl RelativeTime for Date<Gregorian> {
+    fn add_years(self, value: u32) -> Result<Self> {
+        offset_months(self, delta(value)?, 0)
+    }
+
+    fn add_months(self, value: u32) -> Result<Self> {
+        offset_months(self, 0, delta(value)?)
+    }
+
+    fn add_days(self, value: u32) -> Result<Self> {
+        offset(self, 0, 0, delta(value)?)
+    }
+
+    fn add_hours(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Hour))
+    }
+
+    fn add_minutes(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Minute))
+    }
+
+    fn add_seconds(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Second))
+    }
+
+    fn sub_years(self, value: u32) -> Result<Self> {
+        offset_months(self, -delta(value)?, 0)
+    }
+
+    fn sub_months(self, value: u32) -> Result<Self> {
+        offset_months(self, 0, -delta(value)?)
+    }
+
+    fn sub_days(self, value: u32) -> Result<Self> {
+        offset(self, 0, 0, -delta(value)?)
+    }
+
+    fn sub_hours(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Hour))
+    }
+
+    fn sub_minutes(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Minute))
+    }
+
+    fn sub_seconds(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Second))
+    }
+
+    fn floor_years(self) -> Result<Self> {
+        from_iso(self.to_iso().year().number, 1, 1)
+    }
+
+    fn floor_months(self) -> Result<Self> {
+        from_iso(self.to_iso().year().number, month(self)?, 1)
+    }
+
+    fn floor_weeks(self) -> Result<Self> {
+        let days = RelativeTime::day_of_week(&self).map_or(0, Weekday::num_days_from_monday);
+        self.sub_days(days)
+    }
+
+    fn floor_days(self) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn floor_hours(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Hour))
+    }
+
+    fn floor_minutes(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Minute))
+    }
+
+    fn floor_seconds(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Second))
+    }
+
+    fn day_of_month(&self) -> Option<u32> {
+        Some(Self::day_of_month(self).0)
+    }
+
+    fn epoch(&self) -> Option<Self> {
+        from_iso(1970, 1, 1).ok()
+    }
+
+    fn day_of_week(&self) -> Option<Weekday> {
+        // the inherent method of ICU
+        Some(match Self::day_of_week(self) {
+            IsoWeekday::Monday => Weekday::Monday,
+            IsoWeekday::Tuesday => Weekday::Tuesday,
+            IsoWeekday::Wednesday => Weekday::Wednesday,
+            IsoWeekday::Thursday => Weekday::Thursday,
+            IsoWeekday::Friday => Weekday::Friday,
+            IsoWeekday::Saturday => Weekday::Saturday,
+            IsoWeekday::Sunday => Weekday::Sunday,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        MonthOverflow, ParseOptions, parse_str_with_now, parse_str_with_options, parser::Expression,
+    };
+
+    fn date(year: i32, month: u8, day: u8) -> Date<Gregorian> {
+        from_iso(year, month, day).expect("input date invalid")
+    }
+
+    fn parse(input: &str, now: Date<Gregorian>) -> Result<Date<Gregorian>> {
+        parse_str_with_now(input, now)
+    }
+
+    #[test]
+    fn add_month_across_leap_february() {
+        assert_eq!(parse("now+1M", date(2024, 1, 29)), Ok(date(2024, 2, 29)));
+        assert_eq!(parse("now+1M", date(2024, 1, 31)), Ok(date(2024, 2, 29)));
+        assert_eq!(parse("now+1M", date(2023, 1, 31)), Ok(date(2023, 2, 28)));
+        assert_eq!(parse("now-1M", date(2024, 3, 30)), Ok(date(2024, 2, 29)));
+        assert_eq!(parse("now+1y", date(2024, 2, 29)), Ok(date(2025, 2, 28)));
+        assert_eq!(parse("now-13M", date(2024, 1, 31)), Ok(date(2022, 12, 31)));
+    }
+
+    #[test]
+    fn spill_matches_icu() {
+        let options = ParseOptions {
+            month_overflow: MonthOverflow::Spill,
+            ..Default::default()
+        };
+        for (text, now, years, months) in [
+            ("now+1M", date(2024, 1, 31), 0, 1),
+            ("now+1M", date(2023, 1, 31), 0, 1),
+            ("now-1M", date(2024, 3, 30), 0, -1),
+            ("now+1y", date(2024, 2, 29), 1, 0),
+            ("now+25M", date(2023, 5, 31), 0, 25),
+        ] {
+            // the arithmetic of ICU itself
+            assert_eq!(
+                parse_str_with_options(text, now, options),
+                offset(now, years, months, 0),
+                "{text} from {now:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn days() {
+        assert_eq!(parse("now+1d", date(2024, 2, 28)), Ok(date(2024, 2, 29)));
+        assert_eq!(parse("now+2w", date(2023, 12, 25)), Ok(date(2024, 1, 8)));
+        assert_eq!(parse("now-1d", date(1, 1, 1)), Ok(date(0, 12, 31)));
+    }
+
+    #[test]
+    fn floors() {
+        // 2023-08-23 is a wednesday
+        assert_eq!(parse("now/w", date(2023, 8, 23)), Ok(date(2023, 8, 21)));
+        assert_eq!(parse("now/d", date(2023, 8, 23)), Ok(date(2023, 8, 23)));
+        assert_eq!(parse("now/M", date(2023, 8, 23)), Ok(date(2023, 8, 1)));
+        assert_eq!(parse("now/y", date(2023, 8, 23)), Ok(date(2023, 1, 1)));
+        assert_eq!(parse("now/y", date(-5, 8, 23)), Ok(date(-5, 1, 1)));
+    }
+
+    #[test]
+    fn epoch() {
+        assert_eq!(parse("epoch+1d", date(2023, 8, 23)), Ok(date(1970, 1, 2)));
+    }
+
+    #[test]
+    fn weekday() {
+        assert_eq!(
+            parse("now next mon", date(2023, 8, 23)),
+            Ok(date(2023, 8, 28))
+        );
+    }
+
+    #[test]
+    fn unsupported() {
+        let now = date(2023, 8, 23);
+        assert_eq!(
+            parse("now+1h", now),
+            Err(Error::UnsupportedUnit(Unit::Hour))
+        );
+        assert_eq!(
+            parse("now-1m", now),
+            Err(Error::UnsupportedUnit(Unit::Minute))
+        );
+        assert_eq!(
+            parse("now/s", now),
+            Err(Error::UnsupportedUnit(Unit::Second))
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let now = date(2023, 8, 23);
+        assert_eq!(parse("now+4294967295d", now), Err(Error::InvalidDelta));
+        assert_eq!(
+            parse("now+2147483000y", now),
+            Err(Error::OverflowAt(
+                3,
+                Expression::Add(2_147_483_000, Unit::Year)
+            ))
+        );
+        assert_eq!(
+            parse("now-2147483000y", now),
+            Ok(date(-2_147_480_977, 8, 23))
+        );
+    }
+}
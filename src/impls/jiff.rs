@@ -0,0 +1,277 @@
+use jiff::{
+    RoundMode, Span,
+    civil::{self, DateTime, DateTimeRound},
+};
+
+use crate::{
+    RelativeTime,
+    error::{Error, Result},
+    parser::Weekday,
+};
+
+fn add(time: DateTime, span: Result<Span, jiff::Error>) -> Result<DateTime> {
+    let span = span.map_err(|_err| Error::InvalidDelta)?;
+    time.checked_add(span)
+        .map_err(|_err| Error::InvalidTimestamp)
+}
+
+fn sub(time: DateTime, span: Result<Span, jiff::Error>) -> Result<DateTime> {
+    let span = span.map_err(|_err| Error::InvalidDelta)?;
+    time.checked_sub(span)
+        .map_err(|_err| Error::InvalidTimestamp)
+}
+
+fn truncate(time: DateTime, unit: jiff::Unit) -> Result<DateTime> {
+    time.round(DateTimeRound::new().smallest(unit).mode(RoundMode::Trunc))
+        .map_err(|_err| Error::InvalidTimestamp)
+}
+
+/// A civil date and time of jiff, without a time zone, in the years -9999 to
+/// 9999 that jiff supports. Years and months clamp to the end of the month.
+impl RelativeTime for DateTime {
+    fn add_years(self, value: u32) -> Result<Self> {
+        add(self, Span::new().try_years(value))
+    }
+
+    fn add_months(self, value: u32) -> Result<Self> {
+        add(self, Span::new().try_months(value))
+    }
+
+    fn add_weeks(self, value: u32) -> Result<Self> {
+        add(self, Span::new().try_weeks(value))
+    }
+
+    fn add_days(self, value: u32) -> Result<Self> {
+        add(self, Span::new().try_days(value))
+    }
+
+    fn add_hours(self, value: u32) -> Result<Self> {
+        add(self, Span::new().try_hours(value))
+    }
+
+    fn add_minutes(self, value: u32) -> Result<Self> {
+        add(self, Span::new().try_minutes(value))
+    }
+
+    fn add_seconds(self, value: u32) -> Result<Self> {
+        add(self, Span::new().try_seconds(value))
+    }
+
+    fn sub_years(self, value: u32) -> Result<Self> {
+        sub(self, Span::new().try_years(value))
+    }
+
+    fn sub_months(self, value: u32) -> Result<Self> {
+        sub(self, Span::new().try_months(value))
+    }
+
+    fn sub_weeks(self, value: u32) -> Result<Self> {
+        sub(self, Span::new().try_weeks(value))
+    }
+
+    fn sub_days(self, value: u32) -> Result<Self> {
+        sub(self, Span::new().try_days(value))
+    }
+
+    fn sub_hours(self, value: u32) -> Result<Self> {
+        sub(self, Span::new().try_hours(value))
+    }
+
+    fn sub_minutes(self, value: u32) -> Result<Self> {
+        sub(self, Span::new().try_minutes(value))
+    }
+
+    fn sub_seconds(self, value: u32) -> Result<Self> {
+        sub(self, Span::new().try_seconds(value))
+    }
+
+    fn sub_nanos(self, value: u32) -> Result<Self> {
+        sub(self, Span::new().try_nanoseconds(value))
+    }
+
+    fn add_nanos(self, value: u32) -> Result<Self> {
+        add(self, Span::new().try_nanoseconds(value))
+    }
+
+    fn add_millis(self, value: u32) -> Result<Self> {
+        add(self, Span::new().try_milliseconds(value))
+    }
+
+    fn sub_millis(self, value: u32) -> Result<Self> {
+        sub(self, Span::new().try_milliseconds(value))
+    }
+
+    fn add_micros(self, value: u32) -> Result<Self> {
+        add(self, Span::new().try_microseconds(value))
+    }
+
+    fn sub_micros(self, value: u32) -> Result<Self> {
+        sub(self, Span::new().try_microseconds(value))
+    }
+
+    fn floor_years(self) -> Result<Self> {
+        Ok(self.first_of_year().start_of_day())
+    }
+
+    fn floor_months(self) -> Result<Self> {
+        Ok(self.first_of_month().start_of_day())
+    }
+
+    fn floor_weeks(self) -> Result<Self> {
+        let days = u32::from(self.weekday().to_monday_zero_offset().unsigned_abs());
+        self.start_of_day().sub_days(days)
+    }
+
+    fn floor_days(self) -> Result<Self> {
+        Ok(self.start_of_day())
+    }
+
+    fn floor_hours(self) -> Result<Self> {
+        truncate(self, jiff::Unit::Hour)
+    }
+
+    fn floor_minutes(self) -> Result<Self> {
+        truncate(self, jiff::Unit::Minute)
+    }
+
+    fn floor_seconds(self) -> Result<Self> {
+        truncate(self, jiff::Unit::Second)
+    }
+
+    fn floor_millis(self) -> Result<Self> {
+        truncate(self, jiff::Unit::Millisecond)
+    }
+
+    fn floor_micros(self) -> Result<Self> {
+        truncate(self, jiff::Unit::Microsecond)
+    }
+
+    fn day_of_month(&self) -> Option<u32> {
+        u32::try_from(self.day()).ok()
+    }
+
+    fn epoch(&self) -> Option<Self> {
+        Some(civil::date(1970, 1, 1).at(0, 0, 0, 0))
+    }
+
+    fn day_of_week(&self) -> Option<Weekday> {
+        Some(match self.weekday() {
+            civil::Weekday::Monday => Weekday::Monday,
+            civil::Weekday::Tuesday => Weekday::Tuesday,
+            civil::Weekday::Wednesday => Weekday::Wednesday,
+            civil::Weekday::Thursday => Weekday::Thursday,
+            civil::Weekday::Friday => Weekday::Friday,
+            civil::Weekday::Saturday => Weekday::Saturday,
+            civil::Weekday::Sunday => Weekday::Sunday,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        parse_str_with_now,
+        parser::{Expression, Unit},
+    };
+
+    fn datetime(text: &str) -> DateTime {
+        text.parse().expect("input date time invalid")
+    }
+
+    fn parse(input: &str, now: &str) -> Result<DateTime> {
+        parse_str_with_now(input, datetime(now))
+    }
+
+    #[test]
+    fn offsets() {
+        let now = "2023-08-21T05:40:00";
+        assert_eq!(
+            parse("now+1d-2h+30m", now),
+            Ok(datetime("2023-08-22T04:10:00"))
+        );
+        assert_eq!(parse("now-1w", now), Ok(datetime("2023-08-14T05:40:00")));
+        assert_eq!(parse("now+90s", now), Ok(datetime("2023-08-21T05:41:30")));
+        assert_eq!(
+            parse("now+1M", "2024-01-31T12:00:00"),
+            Ok(datetime("2024-02-29T12:00:00"))
+        );
+        assert_eq!(
+            parse("now-1y", "2024-02-29T12:00:00"),
+            Ok(datetime("2023-02-28T12:00:00"))
+        );
+    }
+
+    #[test]
+    fn floors() {
+        // 2023-08-23 is a wednesday
+        let now = "2023-08-23T05:40:30.5";
+        assert_eq!(parse("now/y", now), Ok(datetime("2023-01-01T00:00:00")));
+        assert_eq!(parse("now/M", now), Ok(datetime("2023-08-01T00:00:00")));
+        assert_eq!(parse("now/w", now), Ok(datetime("2023-08-21T00:00:00")));
+        assert_eq!(parse("now/d", now), Ok(datetime("2023-08-23T00:00:00")));
+        assert_eq!(parse("now/h", now), Ok(datetime("2023-08-23T05:00:00")));
+        assert_eq!(parse("now/m", now), Ok(datetime("2023-08-23T05:40:00")));
+        assert_eq!(parse("now/s", now), Ok(datetime("2023-08-23T05:40:30")));
+        assert_eq!(
+            parse("now next mon", now),
+            Ok(datetime("2023-08-28T05:40:30.5"))
+        );
+    }
+
+    #[test]
+    fn epoch() {
+        let now = "2023-08-21T05:40:00";
+        assert_eq!(parse("epoch", now), Ok(datetime("1970-01-01T00:00:00")));
+        assert_eq!(parse("epoch+1d", now), Ok(datetime("1970-01-02T00:00:00")));
+    }
+
+    #[test]
+    fn overflow() {
+        let now = "2023-08-21T05:40:00";
+        assert_eq!(parse("now+4294967295d", now), Err(Error::InvalidDelta));
+        assert_eq!(
+            parse("now+19998y", now),
+            Err(Error::OverflowAt(3, Expression::Add(19_998, Unit::Year)))
+        );
+        assert_eq!(
+            parse("now-1s", "-009999-01-01T00:00:00"),
+            Err(Error::OverflowAt(3, Expression::Sub(1, Unit::Second)))
+        );
+    }
+
+    // Month arithmetic of jiff is exact, so it serves as a reference for the
+    // components of the nanos backend.
+    #[cfg(feature = "nanos")]
+    #[test]
+    fn agrees_with_components() {
+        for now in [
+            "2023-01-31T12:00:00",
+            "2024-01-31T00:00:00",
+            "2024-02-29T23:59:59",
+            "2023-12-31T05:40:00",
+            "1969-07-20T20:17:40.5",
+            "0001-03-31T00:00:00",
+        ] {
+            let now = datetime(now);
+            let nanos = now
+                .to_zoned(jiff::tz::TimeZone::UTC)
+                .expect("input date time invalid")
+                .timestamp()
+                .as_nanosecond();
+            for text in ["now+1M", "now-1M", "now+13M", "now+1y"] {
+                let expected = parse_str_with_now(text, now)
+                    .and_then(|time| {
+                        time.to_zoned(jiff::tz::TimeZone::UTC)
+                            .map_err(|_err| Error::InvalidTimestamp)
+                    })
+                    .map(|time| time.timestamp().as_nanosecond());
+                assert_eq!(
+                    parse_str_with_now(text, nanos),
+                    expected,
+                    "{text} from {now}"
+                );
+            }
+        }
+    }
+}
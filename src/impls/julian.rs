@@ -0,0 +1,221 @@
+use crate::{
+    DAYS_PER_WEEK, RelativeTime,
+    error::{Error, Result},
+    parser::{Unit, Weekday},
+};
+
+/// The Julian Day Number of `1970-01-01`.
+const UNIX_EPOCH: i64 = 2_440_588;
+const NANOS_PER_DAY: i128 = 86_400_000_000_000;
+
+/// A Julian Day Number, the count of days since the start of the julian
+/// period, e.g. `2_460_178` for `2023-08-21`. Only days and larger units are
+/// supported, calendar units and weekdays follow the proleptic gregorian
+/// calendar. Any `i64` day can be represented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JulianDay(pub i64);
+
+impl JulianDay {
+    fn offset(self, days: i64) -> Result<Self> {
+        self.0
+            .checked_add(days)
+            .map(Self)
+            .ok_or(Error::InvalidTimestamp)
+    }
+
+    // Resolves calendar units through the nanosecond backend, starting at
+    // midnight of the day.
+    fn civil(self, op: impl FnOnce(i128) -> Result<i128>) -> Result<Self> {
+        let nanos = (i128::from(self.0) - i128::from(UNIX_EPOCH)) * NANOS_PER_DAY;
+        let days = op(nanos)?.div_euclid(NANOS_PER_DAY);
+        i64::try_from(days)
+            .ok()
+            .and_then(|days| days.checked_add(UNIX_EPOCH))
+            .map(Self)
+            .ok_or(Error::InvalidTimestamp)
+    }
+}
+
+impl RelativeTime for JulianDay {
+    fn add_months(self, value: u32) -> Result<Self> {
+        self.civil(|nanos| nanos.add_months(value))
+    }
+
+    fn add_days(self, value: u32) -> Result<Self> {
+        self.offset(value.into())
+    }
+
+    fn add_hours(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Hour))
+    }
+
+    fn add_minutes(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Minute))
+    }
+
+    fn add_seconds(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Second))
+    }
+
+    fn sub_months(self, value: u32) -> Result<Self> {
+        self.civil(|nanos| nanos.sub_months(value))
+    }
+
+    fn sub_days(self, value: u32) -> Result<Self> {
+        self.offset(-i64::from(value))
+    }
+
+    fn sub_hours(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Hour))
+    }
+
+    fn sub_minutes(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Minute))
+    }
+
+    fn sub_seconds(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Second))
+    }
+
+    fn floor_years(self) -> Result<Self> {
+        self.civil(RelativeTime::floor_years)
+    }
+
+    fn floor_months(self) -> Result<Self> {
+        self.civil(RelativeTime::floor_months)
+    }
+
+    fn floor_weeks(self) -> Result<Self> {
+        // day 0 of the julian period was a monday
+        self.offset(-self.0.rem_euclid(DAYS_PER_WEEK.into()))
+    }
+
+    fn floor_days(self) -> Result<Self> {
+        Ok(self)
+    }
+
+    fn floor_hours(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Hour))
+    }
+
+    fn floor_minutes(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Minute))
+    }
+
+    fn floor_seconds(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Second))
+    }
+
+    fn day_of_week(&self) -> Option<Weekday> {
+        Some(match self.0.rem_euclid(DAYS_PER_WEEK.into()) {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        })
+    }
+
+    fn day_of_month(&self) -> Option<u32> {
+        let day = self.floor_months().ok()?;
+        u32::try_from(self.0 - day.0 + 1).ok()
+    }
+
+    fn epoch(&self) -> Option<Self> {
+        Some(Self(UNIX_EPOCH))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::{Datelike, NaiveDate};
+
+    use super::*;
+    use crate::parse_str_with_now;
+
+    // The Julian Day Number of `0001-01-01` minus one, as chrono counts days
+    // from there.
+    const CE_OFFSET: i64 = 1_721_425;
+
+    fn from_chrono(date: NaiveDate) -> JulianDay {
+        JulianDay(i64::from(date.num_days_from_ce()) + CE_OFFSET)
+    }
+
+    fn to_chrono(day: JulianDay) -> Option<NaiveDate> {
+        i32::try_from(day.0 - CE_OFFSET)
+            .ok()
+            .and_then(NaiveDate::from_num_days_from_ce_opt)
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).expect("input date invalid")
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(from_chrono(date(2023, 8, 21)), JulianDay(2_460_178));
+        assert_eq!(from_chrono(date(1970, 1, 1)), JulianDay(UNIX_EPOCH));
+        // the start of the julian period, in the proleptic gregorian calendar
+        assert_eq!(from_chrono(date(-4713, 11, 24)), JulianDay(0));
+        for day in [0, UNIX_EPOCH, 2_460_178] {
+            assert_eq!(
+                to_chrono(JulianDay(day)).map(from_chrono),
+                Some(JulianDay(day))
+            );
+        }
+    }
+
+    #[test]
+    fn add_day() {
+        let now = JulianDay(2_460_178);
+        assert_eq!(parse_str_with_now("now+1d", now), Ok(JulianDay(2_460_179)));
+        assert_eq!(parse_str_with_now("now-2w", now), Ok(JulianDay(2_460_164)));
+        assert_eq!(parse_str_with_now("epoch", now), Ok(JulianDay(UNIX_EPOCH)));
+        assert_eq!(
+            parse_str_with_now("now+1h", now),
+            Err(Error::UnsupportedUnit(Unit::Hour))
+        );
+    }
+
+    #[test]
+    fn matches_chrono() {
+        let now = date(2024, 1, 31);
+        for text in [
+            "now+1M",
+            "now-1y",
+            "now/w",
+            "now/M",
+            "now/y",
+            "now next sun",
+            "now+13M/w",
+        ] {
+            assert_eq!(
+                parse_str_with_now(text, from_chrono(now)).map(to_chrono),
+                parse_str_with_now(
+                    text,
+                    now.and_hms_opt(0, 0, 0).expect("time invalid").and_utc()
+                )
+                .map(|res| Some(res.date_naive())),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            RelativeTime::day_of_month(&from_chrono(now)),
+            Some(now.day())
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(
+            JulianDay(i64::MAX).add_days(1),
+            Err(Error::InvalidTimestamp)
+        );
+        assert_eq!(
+            JulianDay(i64::MAX).add_months(1),
+            Err(Error::InvalidTimestamp)
+        );
+    }
+}
@@ -1,6 +1,25 @@
+#[cfg(feature = "bson")]
+pub mod bson;
 #[cfg(feature = "chrono")]
 pub mod chrono;
 #[cfg(feature = "chrono-local")]
 pub mod chrono_local;
+#[cfg(feature = "chrono")]
+pub mod chrono_time;
+#[cfg(feature = "chrono-tz")]
+pub mod chrono_tz;
 #[cfg(feature = "chrono-utc")]
 pub mod chrono_utc;
+#[cfg(feature = "chrono")]
+pub mod chrono_zoned;
+#[cfg(feature = "db")]
+pub mod db;
+pub mod duration;
+#[cfg(feature = "icu")]
+pub mod icu;
+#[cfg(feature = "jiff")]
+pub mod jiff;
+#[cfg(feature = "julian")]
+pub mod julian;
+#[cfg(feature = "nanos")]
+pub mod nanos;
@@ -0,0 +1,1099 @@
+//! Nanoseconds since the unix epoch, as an `i128`, in UTC. The calendar of the
+//! dates can be replaced through [`CalendarTime`]. An `i128` covers about
+//! 5 * 10^21 years on either side of the epoch.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    DAYS_PER_WEEK, MONTHS_PER_YEAR, RelativeTime, RelativeTimeNow,
+    error::{Error, Result},
+    parser::{Unit, Weekday},
+    scale,
+};
+
+const NANOS_PER_MICRO: i128 = 1_000;
+const NANOS_PER_MILLI: i128 = 1_000_000;
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+const NANOS_PER_MINUTE: i128 = 60 * NANOS_PER_SECOND;
+const NANOS_PER_HOUR: i128 = 60 * NANOS_PER_MINUTE;
+const NANOS_PER_DAY: i128 = 24 * NANOS_PER_HOUR;
+const NANOS_PER_WEEK: i128 = 7 * NANOS_PER_DAY;
+
+// 1970-01-01 was a thursday
+const EPOCH_DAYS_FROM_MONDAY: i128 = 3;
+
+// Days since the epoch of a date in the proleptic gregorian calendar, from
+// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i128, month: i128, day: i128) -> i128 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// The inverse of `days_from_civil`, returning the year, month and day.
+fn civil_from_days(days: i128) -> (i128, i128, i128) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = if month <= 2 {
+        year_of_era + era * 400 + 1
+    } else {
+        year_of_era + era * 400
+    };
+    (year, month, day)
+}
+
+/// The dates of a calendar, for which [`CalendarTime`] implements
+/// [`RelativeTime`]. Days are always 24 hours, and weeks are the 7 days from
+/// monday, counted from the epoch, which is a thursday.
+pub trait Calendar: Copy + PartialEq {
+    /// The year, month and day of the day with the given index, where day `0`
+    /// is the day of the epoch. Months and days count from 1.
+    fn to_civil(self, days: i128) -> (i128, i128, i128);
+
+    /// The inverse of [`Calendar::to_civil`], the index of the day.
+    fn to_days(self, year: i128, month: i128, day: i128) -> i128;
+
+    fn months_per_year(self) -> u32 {
+        MONTHS_PER_YEAR
+    }
+
+    fn days_in_month(self, year: i128, month: i128) -> i128 {
+        let (next_year, next_month) = if month == self.months_per_year().into() {
+            (year + 1, 1)
+        } else {
+            (year, month + 1)
+        };
+        self.to_days(next_year, next_month, 1) - self.to_days(year, month, 1)
+    }
+
+    /// Moves the day with the given index by a signed number of months,
+    /// clamping the day to the end of the resulting month.
+    fn add_months(self, days: i128, months: i128) -> i128 {
+        let (year, month, day) = self.to_civil(days);
+        let months_per_year = i128::from(self.months_per_year());
+        let total = year * months_per_year + month - 1 + months;
+        let year = total.div_euclid(months_per_year);
+        let month = total.rem_euclid(months_per_year) + 1;
+        self.to_days(year, month, 1) + day.min(self.days_in_month(year, month)) - 1
+    }
+}
+
+/// The proleptic gregorian calendar, as used by `i128` timestamps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Gregorian;
+
+impl Calendar for Gregorian {
+    fn to_civil(self, days: i128) -> (i128, i128, i128) {
+        civil_from_days(days)
+    }
+
+    fn to_days(self, year: i128, month: i128, day: i128) -> i128 {
+        days_from_civil(year, month, day)
+    }
+
+    fn days_in_month(self, year: i128, month: i128) -> i128 {
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        match month {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+}
+
+/// Nanoseconds since the unix epoch, like `i128` timestamps, of which the
+/// dates are in the given calendar.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CalendarTime<C> {
+    pub nanos: i128,
+    pub calendar: C,
+}
+
+impl<C: Calendar> CalendarTime<C> {
+    pub fn new(nanos: i128, calendar: C) -> Self {
+        Self { nanos, calendar }
+    }
+
+    fn days(self) -> i128 {
+        self.nanos.div_euclid(NANOS_PER_DAY)
+    }
+
+    fn with_nanos(self, nanos: i128) -> Self {
+        Self { nanos, ..self }
+    }
+
+    // Moves to the start of the day with the given index, or to the same time
+    // of day with `time_of_day`.
+    fn with_days(self, days: i128, time_of_day: bool) -> Result<Self> {
+        let time = if time_of_day {
+            self.nanos.rem_euclid(NANOS_PER_DAY)
+        } else {
+            0
+        };
+        days.checked_mul(NANOS_PER_DAY)
+            .and_then(|start| start.checked_add(time))
+            .map(|nanos| self.with_nanos(nanos))
+            .ok_or(Error::InvalidTimestamp)
+    }
+
+    fn offset_months(self, months: i128) -> Result<Self> {
+        self.with_days(self.calendar.add_months(self.days(), months), true)
+    }
+
+    // Truncates toward negative infinity, so pre-epoch values move back in
+    // time.
+    fn floor(self, unit: i128) -> Result<Self> {
+        self.floor_moved(unit).map(|(floored, _)| floored)
+    }
+
+    // Like `floor`, also reporting whether there was anything to truncate.
+    fn floor_moved(self, unit: i128) -> Result<(Self, bool)> {
+        let rem = self.nanos.rem_euclid(unit);
+        self.nanos
+            .checked_sub(rem)
+            .map(|nanos| (self.with_nanos(nanos), rem != 0))
+            .ok_or(Error::InvalidTimestamp)
+    }
+
+    fn days_from_monday(self) -> i128 {
+        (self.days() + EPOCH_DAYS_FROM_MONDAY).rem_euclid(DAYS_PER_WEEK.into())
+    }
+
+    fn offset(self, value: u32, unit: i128) -> Result<Self> {
+        i128::from(value)
+            .checked_mul(unit)
+            .and_then(|delta| self.nanos.checked_add(delta))
+            .map(|nanos| self.with_nanos(nanos))
+            .ok_or(Error::InvalidTimestamp)
+    }
+
+    fn offset_back(self, value: u32, unit: i128) -> Result<Self> {
+        i128::from(value)
+            .checked_mul(unit)
+            .and_then(|delta| self.nanos.checked_sub(delta))
+            .map(|nanos| self.with_nanos(nanos))
+            .ok_or(Error::InvalidTimestamp)
+    }
+}
+
+impl<C: Calendar> RelativeTime for CalendarTime<C> {
+    fn add_years(self, value: u32) -> Result<Self> {
+        self.add_months(scale(value, self.calendar.months_per_year())?)
+    }
+
+    fn add_months(self, value: u32) -> Result<Self> {
+        self.offset_months(value.into())
+    }
+
+    fn add_weeks(self, value: u32) -> Result<Self> {
+        self.offset(value, NANOS_PER_WEEK)
+    }
+
+    fn add_days(self, value: u32) -> Result<Self> {
+        self.offset(value, NANOS_PER_DAY)
+    }
+
+    fn add_hours(self, value: u32) -> Result<Self> {
+        self.offset(value, NANOS_PER_HOUR)
+    }
+
+    fn add_minutes(self, value: u32) -> Result<Self> {
+        self.offset(value, NANOS_PER_MINUTE)
+    }
+
+    fn add_seconds(self, value: u32) -> Result<Self> {
+        self.offset(value, NANOS_PER_SECOND)
+    }
+
+    fn sub_years(self, value: u32) -> Result<Self> {
+        self.sub_months(scale(value, self.calendar.months_per_year())?)
+    }
+
+    fn sub_months(self, value: u32) -> Result<Self> {
+        self.offset_months(-i128::from(value))
+    }
+
+    fn sub_weeks(self, value: u32) -> Result<Self> {
+        self.offset_back(value, NANOS_PER_WEEK)
+    }
+
+    fn sub_days(self, value: u32) -> Result<Self> {
+        self.offset_back(value, NANOS_PER_DAY)
+    }
+
+    fn sub_hours(self, value: u32) -> Result<Self> {
+        self.offset_back(value, NANOS_PER_HOUR)
+    }
+
+    fn sub_minutes(self, value: u32) -> Result<Self> {
+        self.offset_back(value, NANOS_PER_MINUTE)
+    }
+
+    fn sub_seconds(self, value: u32) -> Result<Self> {
+        self.offset_back(value, NANOS_PER_SECOND)
+    }
+
+    fn sub_nanos(self, value: u32) -> Result<Self> {
+        self.offset_back(value, 1)
+    }
+
+    fn add_nanos(self, value: u32) -> Result<Self> {
+        self.offset(value, 1)
+    }
+
+    fn add_millis(self, value: u32) -> Result<Self> {
+        self.offset(value, NANOS_PER_MILLI)
+    }
+
+    fn sub_millis(self, value: u32) -> Result<Self> {
+        self.offset_back(value, NANOS_PER_MILLI)
+    }
+
+    fn add_micros(self, value: u32) -> Result<Self> {
+        self.offset(value, NANOS_PER_MICRO)
+    }
+
+    fn sub_micros(self, value: u32) -> Result<Self> {
+        self.offset_back(value, NANOS_PER_MICRO)
+    }
+
+    fn floor_years(self) -> Result<Self> {
+        let (year, _, _) = self.calendar.to_civil(self.days());
+        self.with_days(self.calendar.to_days(year, 1, 1), false)
+    }
+
+    fn floor_months(self) -> Result<Self> {
+        let (year, month, _) = self.calendar.to_civil(self.days());
+        self.with_days(self.calendar.to_days(year, month, 1), false)
+    }
+
+    fn floor_weeks(self) -> Result<Self> {
+        self.floor(NANOS_PER_DAY)?
+            .offset_back(1, self.days_from_monday() * NANOS_PER_DAY)
+    }
+
+    fn floor_days(self) -> Result<Self> {
+        self.floor(NANOS_PER_DAY)
+    }
+
+    fn floor_hours(self) -> Result<Self> {
+        self.floor(NANOS_PER_HOUR)
+    }
+
+    fn floor_minutes(self) -> Result<Self> {
+        self.floor(NANOS_PER_MINUTE)
+    }
+
+    fn floor_seconds(self) -> Result<Self> {
+        self.floor(NANOS_PER_SECOND)
+    }
+
+    fn floor_millis(self) -> Result<Self> {
+        self.floor(NANOS_PER_MILLI)
+    }
+
+    fn floor_micros(self) -> Result<Self> {
+        self.floor(NANOS_PER_MICRO)
+    }
+
+    // Whether the floor moved follows from the parts it truncates, without
+    // comparing the result.
+    fn floor_checked(self, unit: Unit) -> Result<(Self, bool)> {
+        let into_day = self.nanos.rem_euclid(NANOS_PER_DAY) != 0;
+        match unit {
+            Unit::Year => {
+                let (year, month, day) = self.calendar.to_civil(self.days());
+                let floored = self.with_days(self.calendar.to_days(year, 1, 1), false)?;
+                Ok((floored, into_day || month != 1 || day != 1))
+            }
+            Unit::Month => {
+                let (year, month, day) = self.calendar.to_civil(self.days());
+                let floored = self.with_days(self.calendar.to_days(year, month, 1), false)?;
+                Ok((floored, into_day || day != 1))
+            }
+            Unit::Week => {
+                let days_from_monday = self.days_from_monday();
+                let floored = self
+                    .floor(NANOS_PER_DAY)?
+                    .offset_back(1, days_from_monday * NANOS_PER_DAY)?;
+                Ok((floored, into_day || days_from_monday != 0))
+            }
+            Unit::Day => self.floor_moved(NANOS_PER_DAY),
+            Unit::Hour => self.floor_moved(NANOS_PER_HOUR),
+            Unit::Minute => self.floor_moved(NANOS_PER_MINUTE),
+            Unit::Second => self.floor_moved(NANOS_PER_SECOND),
+            Unit::Millisecond => self.floor_moved(NANOS_PER_MILLI),
+            Unit::Microsecond => self.floor_moved(NANOS_PER_MICRO),
+        }
+    }
+
+    fn day_of_month(&self) -> Option<u32> {
+        let (_, _, day) = self.calendar.to_civil(self.days());
+        u32::try_from(day).ok()
+    }
+
+    fn epoch(&self) -> Option<Self> {
+        Some(self.with_nanos(0))
+    }
+
+    fn day_of_week(&self) -> Option<Weekday> {
+        Some(match self.days_from_monday() {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        })
+    }
+}
+
+// Applies `op` to `nanos` in the gregorian calendar.
+fn gregorian(
+    nanos: i128,
+    op: impl FnOnce(CalendarTime<Gregorian>) -> Result<CalendarTime<Gregorian>>,
+) -> Result<i128> {
+    op(CalendarTime::new(nanos, Gregorian)).map(|time| time.nanos)
+}
+
+impl RelativeTime for i128 {
+    fn add_months(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.add_months(value))
+    }
+
+    fn add_weeks(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.add_weeks(value))
+    }
+
+    fn add_days(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.add_days(value))
+    }
+
+    fn add_hours(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.add_hours(value))
+    }
+
+    fn add_minutes(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.add_minutes(value))
+    }
+
+    fn add_seconds(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.add_seconds(value))
+    }
+
+    fn sub_months(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.sub_months(value))
+    }
+
+    fn sub_weeks(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.sub_weeks(value))
+    }
+
+    fn sub_days(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.sub_days(value))
+    }
+
+    fn sub_hours(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.sub_hours(value))
+    }
+
+    fn sub_minutes(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.sub_minutes(value))
+    }
+
+    fn sub_seconds(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.sub_seconds(value))
+    }
+
+    fn sub_nanos(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.sub_nanos(value))
+    }
+
+    fn add_nanos(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.add_nanos(value))
+    }
+
+    fn add_millis(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.add_millis(value))
+    }
+
+    fn sub_millis(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.sub_millis(value))
+    }
+
+    fn add_micros(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.add_micros(value))
+    }
+
+    fn sub_micros(self, value: u32) -> Result<Self> {
+        gregorian(self, |time| time.sub_micros(value))
+    }
+
+    fn floor_years(self) -> Result<Self> {
+        gregorian(self, CalendarTime::floor_years)
+    }
+
+    fn floor_months(self) -> Result<Self> {
+        gregorian(self, CalendarTime::floor_months)
+    }
+
+    fn floor_weeks(self) -> Result<Self> {
+        gregorian(self, CalendarTime::floor_weeks)
+    }
+
+    fn floor_days(self) -> Result<Self> {
+        gregorian(self, CalendarTime::floor_days)
+    }
+
+    fn floor_hours(self) -> Result<Self> {
+        gregorian(self, CalendarTime::floor_hours)
+    }
+
+    fn floor_minutes(self) -> Result<Self> {
+        gregorian(self, CalendarTime::floor_minutes)
+    }
+
+    fn floor_seconds(self) -> Result<Self> {
+        gregorian(self, CalendarTime::floor_seconds)
+    }
+
+    fn floor_millis(self) -> Result<Self> {
+        gregorian(self, CalendarTime::floor_millis)
+    }
+
+    fn floor_micros(self) -> Result<Self> {
+        gregorian(self, CalendarTime::floor_micros)
+    }
+
+    fn floor_checked(self, unit: Unit) -> Result<(Self, bool)> {
+        CalendarTime::new(self, Gregorian)
+            .floor_checked(unit)
+            .map(|(floored, moved)| (floored.nanos, moved))
+    }
+
+    fn day_of_month(&self) -> Option<u32> {
+        CalendarTime::new(*self, Gregorian).day_of_month()
+    }
+
+    fn day_of_week(&self) -> Option<Weekday> {
+        CalendarTime::new(*self, Gregorian).day_of_week()
+    }
+
+    fn epoch(&self) -> Option<Self> {
+        Some(0)
+    }
+}
+
+impl RelativeTimeNow for i128 {
+    fn now() -> Self {
+        let nanos = |duration: std::time::Duration| {
+            Self::try_from(duration.as_nanos()).unwrap_or(Self::MAX)
+        };
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => nanos(duration),
+            Err(err) => -nanos(err.duration()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        MonthOverflow, ParseOptions, TimeComponents, parse_str_with_now, parse_str_with_options,
+        parser::{Expression, Unit},
+        resolve_exprs,
+    };
+
+    fn to_components(nanos: i128) -> Result<TimeComponents> {
+        let (year, month, day) = Gregorian.to_civil(nanos.div_euclid(NANOS_PER_DAY));
+        let nanos = nanos.rem_euclid(NANOS_PER_DAY);
+        let field = |value: i128| i64::try_from(value).map_err(|_err| Error::InvalidTimestamp);
+        Ok(TimeComponents {
+            years: field(year)?,
+            months: field(month)?,
+            days: field(day)?,
+            hours: field(nanos / NANOS_PER_HOUR)?,
+            minutes: field(nanos % NANOS_PER_HOUR / NANOS_PER_MINUTE)?,
+            seconds: field(nanos % NANOS_PER_MINUTE / NANOS_PER_SECOND)?,
+            nanos: field(nanos % NANOS_PER_SECOND)?,
+        })
+    }
+
+    fn from_components(components: &TimeComponents) -> i128 {
+        let days = Gregorian.to_days(
+            components.years.into(),
+            components.months.into(),
+            components.days.into(),
+        );
+        days * NANOS_PER_DAY
+            + i128::from(components.hours) * NANOS_PER_HOUR
+            + i128::from(components.minutes) * NANOS_PER_MINUTE
+            + i128::from(components.seconds) * NANOS_PER_SECOND
+            + i128::from(components.nanos)
+    }
+
+    // 2023-08-21T05:40:00.123456789Z
+    const NOW: i128 = 1_692_596_400_123_456_789;
+    // 1969-07-20T20:17:40.5Z
+    const BEFORE_EPOCH: i128 = -14_182_939_500_000_000;
+
+    fn parse(input: &str, now: i128) -> Result<i128> {
+        parse_str_with_now(input, now)
+    }
+
+    #[test]
+    fn components_round_trip() {
+        for nanos in [NOW, BEFORE_EPOCH, 0, -1, 951_782_400 * NANOS_PER_SECOND] {
+            let components = to_components(nanos).expect("components should fit");
+            assert_eq!(from_components(&components), nanos);
+        }
+        assert_eq!(
+            to_components(BEFORE_EPOCH),
+            Ok(TimeComponents {
+                years: 1969,
+                months: 7,
+                days: 20,
+                hours: 20,
+                minutes: 17,
+                seconds: 40,
+                nanos: 500_000_000,
+            })
+        );
+        assert_eq!(
+            to_components(-1).map(|components| components.nanos),
+            Ok(999_999_999)
+        );
+    }
+
+    #[test]
+    fn fixed_units() {
+        assert_eq!(parse("now+1s", NOW), Ok(NOW + NANOS_PER_SECOND));
+        assert_eq!(parse("now-1d+2h", NOW), Ok(NOW - 22 * NANOS_PER_HOUR));
+        assert_eq!(
+            parse("now+1w", BEFORE_EPOCH),
+            Ok(BEFORE_EPOCH + NANOS_PER_WEEK)
+        );
+        assert_eq!(
+            parse("now+100000d", 0),
+            Ok(100_000 * NANOS_PER_DAY),
+            "fixed units do not overflow through seconds"
+        );
+        assert_eq!(
+            parse("now+1s", i128::MAX),
+            Err(Error::OverflowAt(3, Expression::Add(1, Unit::Second)))
+        );
+        assert_eq!(
+            parse("now/w", i128::MIN),
+            Err(Error::OverflowAt(3, Expression::Floor(Unit::Week)))
+        );
+        assert_eq!(
+            parse("now+1M", i128::MAX),
+            Err(Error::OverflowAt(3, Expression::Add(1, Unit::Month)))
+        );
+    }
+
+    #[test]
+    fn calendar_units() {
+        // 2023-01-31 + 1M clamps to 2023-02-28
+        let end_of_january = days_from_civil(2023, 1, 31) * NANOS_PER_DAY;
+        assert_eq!(
+            parse("now+1M", end_of_january),
+            Ok(days_from_civil(2023, 2, 28) * NANOS_PER_DAY)
+        );
+        assert_eq!(
+            parse("now-1y", NOW),
+            Ok(NOW - 365 * NANOS_PER_DAY),
+            "2022 is not a leap year"
+        );
+        assert_eq!(
+            parse("now+1y-1y", BEFORE_EPOCH),
+            Ok(BEFORE_EPOCH),
+            "years keep the time of day before the epoch"
+        );
+        assert_eq!(
+            parse("now+6M", BEFORE_EPOCH).and_then(to_components),
+            to_components(BEFORE_EPOCH).map(|components| TimeComponents {
+                years: 1970,
+                months: 1,
+                ..components
+            })
+        );
+    }
+
+    #[test]
+    fn leap_day_years() {
+        let leap_day = days_from_civil(2020, 2, 29) * NANOS_PER_DAY + NANOS_PER_HOUR;
+        for (text, (year, month, day)) in [
+            ("now+1y", (2021, 2, 28)),
+            ("now+4y", (2024, 2, 29)),
+            ("now-1y", (2019, 2, 28)),
+            ("now+1y+3y", (2024, 2, 28)),
+        ] {
+            assert_eq!(
+                parse(text, leap_day),
+                Ok(days_from_civil(year, month, day) * NANOS_PER_DAY + NANOS_PER_HOUR),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn floor_years_every_day() {
+        for year in [-401, -1, 0, 1, 1900, 1969, 1970, 2000, 2023, 2024] {
+            let start = days_from_civil(year, 1, 1);
+            let end = days_from_civil(year + 1, 1, 1);
+            for days in start..end {
+                for time in [0, NANOS_PER_HOUR, NANOS_PER_DAY - 1] {
+                    let nanos = days * NANOS_PER_DAY + time;
+                    assert_eq!(
+                        nanos.floor_years().and_then(to_components),
+                        Ok(TimeComponents {
+                            years: year.try_into().expect("year should fit"),
+                            months: 1,
+                            days: 1,
+                            ..Default::default()
+                        }),
+                        "{:?}",
+                        to_components(nanos)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn truncate_to_micros() {
+        let options = ParseOptions {
+            truncate_to: Some(Unit::Microsecond),
+            ..Default::default()
+        };
+        assert_eq!(parse_str_with_options("now", NOW, options), Ok(NOW - 789));
+        assert_eq!(
+            parse_str_with_options("now-1d", BEFORE_EPOCH - 1, options),
+            Ok(BEFORE_EPOCH - NANOS_PER_DAY - NANOS_PER_MICRO)
+        );
+        let exprs = [
+            Expression::Now(0),
+            Expression::Add(5, Unit::Microsecond),
+            Expression::Sub(3, Unit::Microsecond),
+            Expression::Floor(Unit::Microsecond),
+        ];
+        assert_eq!(
+            resolve_exprs(NOW, exprs),
+            Ok(NOW - 789 + 2 * NANOS_PER_MICRO)
+        );
+        assert_eq!(
+            parse_str_with_options("now", std::time::Duration::ZERO, options),
+            Err(Error::UnsupportedUnit(Unit::Microsecond))
+        );
+    }
+
+    #[test]
+    fn floor_sub_second() {
+        assert_eq!(parse("now/ms", NOW), Ok(NOW - 456_789));
+        assert_eq!(
+            parse("now+2ms/ms", NOW),
+            Ok(NOW - 456_789 + 2 * NANOS_PER_MILLI)
+        );
+        assert_eq!(NOW.floor_micros(), Ok(NOW - 789));
+        // before the epoch the floor still moves back in time
+        assert_eq!(
+            parse("now/ms", BEFORE_EPOCH - 1),
+            Ok(BEFORE_EPOCH - NANOS_PER_MILLI)
+        );
+        assert_eq!(
+            (BEFORE_EPOCH - 1).floor_micros(),
+            Ok(BEFORE_EPOCH - NANOS_PER_MICRO)
+        );
+    }
+
+    #[test]
+    fn end_of_month() {
+        for (year, day, last) in [
+            (2024, 10, 29),
+            (2023, 10, 28),
+            (2024, 1, 29),
+            (1900, 28, 28),
+        ] {
+            let now = days_from_civil(year, 2, day) * NANOS_PER_DAY + NANOS_PER_HOUR;
+            assert_eq!(
+                now.end_of_month().and_then(to_components),
+                Ok(TimeComponents {
+                    years: year.try_into().expect("year should fit"),
+                    months: 2,
+                    days: last,
+                    hours: 23,
+                    minutes: 59,
+                    seconds: 59,
+                    nanos: 999_999_999,
+                }),
+                "{year}-02-{day}"
+            );
+        }
+        assert_eq!(
+            NOW.end_of_day(),
+            Ok(NOW - NOW.rem_euclid(NANOS_PER_DAY) + NANOS_PER_DAY - 1)
+        );
+    }
+
+    #[test]
+    fn many_month_steps() {
+        // from the first of the month no step clamps, so the steps add up
+        let start = days_from_civil(2023, 1, 1) * NANOS_PER_DAY + NANOS_PER_HOUR;
+        let mut time = start;
+        for _ in 0..10_000 {
+            time = time.add_months(1).expect("month step should fit");
+        }
+        assert_eq!(Ok(time), start.add_months(10_000));
+        assert_eq!(
+            to_components(time),
+            Ok(TimeComponents {
+                years: 2856,
+                months: 5,
+                days: 1,
+                hours: 1,
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn month_overflow() {
+        let options = ParseOptions {
+            month_overflow: MonthOverflow::Spill,
+            ..Default::default()
+        };
+        let end_of_january = days_from_civil(2023, 1, 31) * NANOS_PER_DAY + NANOS_PER_HOUR;
+        assert_eq!(
+            parse_str_with_options("now+1M", end_of_january, options),
+            Ok(days_from_civil(2023, 3, 3) * NANOS_PER_DAY + NANOS_PER_HOUR)
+        );
+    }
+
+    #[test]
+    fn month_add_sticky_eom() {
+        let options = ParseOptions {
+            month_add_sticky_eom: true,
+            ..Default::default()
+        };
+        let day =
+            |year, month, day| days_from_civil(year, month, day) * NANOS_PER_DAY + NANOS_PER_HOUR;
+        assert_eq!(
+            parse_str_with_options("now+1M", day(2023, 1, 31), options),
+            Ok(day(2023, 2, 28))
+        );
+        assert_eq!(
+            parse_str_with_options("now+1M+1M", day(2023, 1, 31), options),
+            Ok(day(2023, 3, 31))
+        );
+        assert_eq!(
+            parse_str_with_options("now+1M", day(2024, 2, 28), options),
+            Ok(day(2024, 3, 28))
+        );
+    }
+
+    #[test]
+    fn floors() {
+        assert_eq!(
+            parse("now/s", NOW),
+            Ok(NOW - 123_456_789),
+            "floors drop the sub-second part"
+        );
+        assert_eq!(parse("now/s", -1), Ok(-NANOS_PER_SECOND));
+        assert_eq!(parse("now/d", -1), Ok(-NANOS_PER_DAY));
+        assert_eq!(
+            parse("now/h", BEFORE_EPOCH).and_then(to_components),
+            to_components(BEFORE_EPOCH).map(|components| TimeComponents {
+                minutes: 0,
+                seconds: 0,
+                nanos: 0,
+                ..components
+            })
+        );
+        assert_eq!(
+            parse("now/w", NOW),
+            Ok(days_from_civil(2023, 8, 21) * NANOS_PER_DAY),
+            "the fixture is a monday"
+        );
+        assert_eq!(
+            parse("now/w", BEFORE_EPOCH),
+            Ok(days_from_civil(1969, 7, 14) * NANOS_PER_DAY)
+        );
+        assert_eq!(
+            parse("now/M", BEFORE_EPOCH),
+            Ok(days_from_civil(1969, 7, 1) * NANOS_PER_DAY)
+        );
+        assert_eq!(
+            parse("now/y", BEFORE_EPOCH),
+            Ok(days_from_civil(1969, 1, 1) * NANOS_PER_DAY)
+        );
+    }
+
+    #[test]
+    fn chained_sub_day_floors() {
+        let hour = days_from_civil(2023, 8, 21) * NANOS_PER_DAY + 5 * NANOS_PER_HOUR;
+        let minute = hour + 40 * NANOS_PER_MINUTE;
+        for now in [NOW, BEFORE_EPOCH, hour, minute, 0, -1, -NANOS_PER_HOUR] {
+            let hours = now.floor_hours().expect("floor should fit");
+            let minutes = now.floor_minutes().expect("floor should fit");
+            assert_eq!(hours.floor_minutes(), Ok(hours), "{now}");
+            assert_eq!(minutes.floor_hours(), Ok(hours), "{now}");
+            assert_eq!(hours.floor_hours(), Ok(hours), "{now}");
+            assert_eq!(minutes.floor_minutes(), Ok(minutes), "{now}");
+            assert!(hours <= minutes && minutes <= now, "{now}");
+            assert_eq!(parse("now/h/m", now), Ok(hours), "{now}");
+        }
+        // a value on the boundary stays where it is
+        assert_eq!(hour.floor_hours(), Ok(hour));
+        assert_eq!(minute.floor_minutes(), Ok(minute));
+        assert_eq!(minute.floor_hours(), Ok(hour));
+    }
+
+    #[test]
+    fn floor_checked() {
+        let (floored, moved) = NOW.floor_checked(Unit::Day).expect("floor should fit");
+        assert!(moved);
+        for unit in [Unit::Day, Unit::Hour, Unit::Minute, Unit::Second] {
+            assert_eq!(
+                floored.floor_checked(unit),
+                Ok((floored, false)),
+                "{unit:?}"
+            );
+        }
+        assert_eq!(
+            floored.floor_checked(Unit::Month).map(|(_, moved)| moved),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn floor_checked_matches_comparison() {
+        let year = days_from_civil(2024, 1, 1) * NANOS_PER_DAY;
+        let month = days_from_civil(2024, 3, 1) * NANOS_PER_DAY;
+        // a monday
+        let week = days_from_civil(2023, 8, 21) * NANOS_PER_DAY;
+        for now in [
+            NOW,
+            BEFORE_EPOCH,
+            0,
+            -1,
+            year,
+            month,
+            week,
+            week + NANOS_PER_HOUR,
+            month + NANOS_PER_MILLI,
+        ] {
+            for unit in [
+                Unit::Year,
+                Unit::Month,
+                Unit::Week,
+                Unit::Day,
+                Unit::Hour,
+                Unit::Minute,
+                Unit::Second,
+                Unit::Millisecond,
+                Unit::Microsecond,
+            ] {
+                let floored = match unit {
+                    Unit::Year => now.floor_years(),
+                    Unit::Month => now.floor_months(),
+                    Unit::Week => now.floor_weeks(),
+                    Unit::Day => now.floor_days(),
+                    Unit::Hour => now.floor_hours(),
+                    Unit::Minute => now.floor_minutes(),
+                    Unit::Second => now.floor_seconds(),
+                    Unit::Millisecond => now.floor_millis(),
+                    Unit::Microsecond => now.floor_micros(),
+                };
+                assert_eq!(
+                    now.floor_checked(unit),
+                    floored.map(|floored| (floored, floored != now)),
+                    "{now} {unit:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn floor_matrix() {
+        // 2023-08-23T05:40:30.123456789Z, a wednesday
+        let now = NOW + 2 * NANOS_PER_DAY + 30 * NANOS_PER_SECOND;
+        let components = to_components(now).expect("components should fit");
+        let midnight = TimeComponents {
+            hours: 0,
+            minutes: 0,
+            seconds: 0,
+            nanos: 0,
+            ..components
+        };
+        for (text, expected) in [
+            (
+                "now/y",
+                TimeComponents {
+                    months: 1,
+                    days: 1,
+                    ..midnight
+                },
+            ),
+            (
+                "now/M",
+                TimeComponents {
+                    days: 1,
+                    ..midnight
+                },
+            ),
+            (
+                "now/w",
+                TimeComponents {
+                    days: 21,
+                    ..midnight
+                },
+            ),
+            ("now/d", midnight),
+            (
+                "now/h",
+                TimeComponents {
+                    minutes: 0,
+                    seconds: 0,
+                    nanos: 0,
+                    ..components
+                },
+            ),
+            (
+                "now/m",
+                TimeComponents {
+                    seconds: 0,
+                    nanos: 0,
+                    ..components
+                },
+            ),
+            (
+                "now/s",
+                TimeComponents {
+                    nanos: 0,
+                    ..components
+                },
+            ),
+        ] {
+            assert_eq!(
+                parse(text, now).and_then(to_components),
+                Ok(expected),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn week_floor_clamp_to_month() {
+        let options = ParseOptions {
+            week_floor_clamp_to_month: true,
+            ..Default::default()
+        };
+        // a wednesday, of which the week starts on 2023-10-30
+        let now = days_from_civil(2023, 11, 1) * NANOS_PER_DAY + NANOS_PER_HOUR;
+        assert_eq!(
+            parse_str_with_options("now/w", now, options),
+            Ok(days_from_civil(2023, 11, 1) * NANOS_PER_DAY)
+        );
+    }
+
+    #[test]
+    fn gregorian_days_in_month() {
+        for year in [1900, 2000, 2023, 2024, -4, -1] {
+            for month in 1..=12 {
+                let next = if month == 12 {
+                    days_from_civil(year + 1, 1, 1)
+                } else {
+                    days_from_civil(year, month + 1, 1)
+                };
+                assert_eq!(
+                    Gregorian.days_in_month(year, month),
+                    next - days_from_civil(year, month, 1),
+                    "{year}-{month}"
+                );
+            }
+        }
+    }
+
+    // 13 months of 28 days, starting at the epoch
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Thirteen;
+
+    impl Calendar for Thirteen {
+        fn to_civil(self, days: i128) -> (i128, i128, i128) {
+            let day_of_year = days.rem_euclid(364);
+            (
+                days.div_euclid(364),
+                day_of_year / 28 + 1,
+                day_of_year % 28 + 1,
+            )
+        }
+
+        fn to_days(self, year: i128, month: i128, day: i128) -> i128 {
+            year * 364 + (month - 1) * 28 + day - 1
+        }
+
+        fn months_per_year(self) -> u32 {
+            13
+        }
+    }
+
+    #[test]
+    fn custom_calendar() {
+        let time = |year, month, day| {
+            CalendarTime::new(
+                Thirteen.to_days(year, month, day) * NANOS_PER_DAY + NANOS_PER_HOUR,
+                Thirteen,
+            )
+        };
+        let parse = |text, now| parse_str_with_now(text, now);
+
+        assert_eq!(parse("now+1M", time(5, 12, 20)), Ok(time(5, 13, 20)));
+        assert_eq!(parse("now+1M", time(5, 13, 20)), Ok(time(6, 1, 20)));
+        assert_eq!(parse("now-1M", time(0, 1, 1)), Ok(time(-1, 13, 1)));
+        assert_eq!(parse("now+1y", time(5, 13, 20)), Ok(time(6, 13, 20)));
+        assert_eq!(
+            parse("now/M", time(5, 13, 20)),
+            Ok(CalendarTime::new(
+                Thirteen.to_days(5, 13, 1) * NANOS_PER_DAY,
+                Thirteen
+            ))
+        );
+        assert_eq!(time(5, 13, 20).day_of_month(), Some(20));
+    }
+
+    #[test]
+    fn epoch() {
+        assert_eq!(parse("epoch", NOW), Ok(0));
+        assert_eq!(parse("epoch+19000d", NOW), Ok(19_000 * NANOS_PER_DAY));
+        assert_eq!(
+            parse_str_with_now("epoch+1M", CalendarTime::new(NOW, Thirteen)),
+            Ok(CalendarTime::new(28 * NANOS_PER_DAY, Thirteen))
+        );
+    }
+
+    #[test]
+    fn weekday() {
+        assert_eq!(NOW.day_of_week(), Some(Weekday::Monday));
+        assert_eq!(BEFORE_EPOCH.day_of_week(), Some(Weekday::Sunday));
+        assert_eq!(0.day_of_week(), Some(Weekday::Thursday));
+    }
+}
@@ -1,6 +1,4 @@
-use std::{iter::Peekable, str::CharIndices};
-
-use crate::{Error, Result};
+use crate::{Error, Result, parser::Weekday};
 
 macro_rules! bail {
     ($err:expr) => {
@@ -11,78 +9,372 @@ macro_rules! bail {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Token {
     Now,
+    Epoch,
     Value(u32),
     Add,
     Sub,
     Floor,
+    AssertFloor,
+    Ago,
     Year,
+    /// The ISO 8601 week-numbering year, only used to floor.
+    IsoYear,
     Month,
     Week,
     Day,
     Hour,
     Minute,
     Second,
+    /// `ms`, which is not a minute followed by a second.
+    Millisecond,
+    This,
+    Next,
+    Last,
+    Weekday(Weekday),
+    /// `bh`, only used to floor to business hours.
+    BusinessHours,
+    /// `q`, only used to floor to the quarter hour.
+    QuarterHour,
+    /// `H`, only used to floor to the half hour.
+    HalfHour,
+    /// `@`, which targets a weekday within the week of a week term.
+    At,
+    /// `*`, which repeats the term before it a number of times.
+    Repeat,
+    /// `~`, which jitters the time by up to the term after it.
+    Jitter,
+    /// A run of whitespace of the given length in bytes, only emitted when
+    /// enabled through [`Lexer::with_whitespace`].
+    Whitespace(usize),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Now => write!(f, "now"),
+            Self::Epoch => write!(f, "epoch"),
+            Self::Value(value) => write!(f, "{}", value),
+            Self::Add => write!(f, "+"),
+            Self::Sub => write!(f, "-"),
+            Self::Floor => write!(f, "/"),
+            Self::AssertFloor => write!(f, "|"),
+            Self::Ago => write!(f, "ago"),
+            Self::Year => write!(f, "y"),
+            Self::IsoYear => write!(f, "Y"),
+            Self::Month => write!(f, "M"),
+            Self::Week => write!(f, "w"),
+            Self::Day => write!(f, "d"),
+            Self::Hour => write!(f, "h"),
+            Self::Minute => write!(f, "m"),
+            Self::Second => write!(f, "s"),
+            Self::Millisecond => write!(f, "ms"),
+            Self::This => write!(f, "this"),
+            Self::Next => write!(f, "next"),
+            Self::Last => write!(f, "last"),
+            Self::Weekday(weekday) => match weekday {
+                Weekday::Monday => write!(f, "mon"),
+                Weekday::Tuesday => write!(f, "tue"),
+                Weekday::Wednesday => write!(f, "wed"),
+                Weekday::Thursday => write!(f, "thu"),
+                Weekday::Friday => write!(f, "fri"),
+                Weekday::Saturday => write!(f, "sat"),
+                Weekday::Sunday => write!(f, "sun"),
+            },
+            Self::BusinessHours => write!(f, "bh"),
+            Self::QuarterHour => write!(f, "q"),
+            Self::HalfHour => write!(f, "H"),
+            Self::At => write!(f, "@"),
+            Self::Repeat => write!(f, "*"),
+            Self::Jitter => write!(f, "~"),
+            Self::Whitespace(len) => write!(f, "{:len$}", ""),
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct Lexer<'s> {
-    pub(crate) text: &'s str,
-    chars: Peekable<CharIndices<'s>>,
+    bytes: &'s [u8],
+    // the text the bytes belong to, to report non-ASCII characters, or `None`
+    // when lexing raw bytes
+    text: Option<&'s str>,
+    index: usize,
+    whitespace: bool,
+    strict_case: bool,
+    comments: bool,
 }
 
 impl<'s> Lexer<'s> {
     pub fn new(text: &'s str) -> Self {
         Self {
-            text,
-            chars: text.char_indices().peekable(),
+            bytes: text.as_bytes(),
+            text: Some(text),
+            index: 0,
+            whitespace: false,
+            strict_case: false,
+            comments: false,
         }
     }
+
+    /// Lexes `bytes` directly, without validating them as UTF-8, as all tokens
+    /// are ASCII. Any other byte results in [`Error::UnexpectedByte`].
+    pub fn from_bytes(bytes: &'s [u8]) -> Self {
+        Self {
+            bytes,
+            text: None,
+            index: 0,
+            whitespace: false,
+            strict_case: false,
+            comments: false,
+        }
+    }
+
+    // The character at the current position, or `None` at the end of the input
+    // or at a non-ASCII byte of raw bytes.
+    fn peek_char(&self) -> Option<char> {
+        let byte = *self.bytes.get(self.index)?;
+        if byte.is_ascii() {
+            Some(char::from(byte))
+        } else {
+            self.text?.get(self.index..)?.chars().next()
+        }
+    }
+
+    // Consumes whatever is at the current position, which was not expected.
+    fn unexpected(&mut self) -> Error {
+        let index = self.index;
+        match (self.peek_char(), self.bytes.get(index)) {
+            (Some(c), _) => {
+                self.index += c.len_utf8();
+                Error::UnexpectedCharacter(index, c)
+            }
+            (None, Some(byte)) => {
+                self.index += 1;
+                Error::UnexpectedByte(index, *byte)
+            }
+            (None, None) => Error::UnexpectedCharacter(index, '\u{3}'), // 3 is EOT
+        }
+    }
+
+    // Consumes the remainder of `keyword`, of which the first character has
+    // already been consumed.
+    fn keyword(&mut self, keyword: &str) -> Result<()> {
+        for expected in keyword.bytes().skip(1) {
+            if self.bytes.get(self.index) != Some(&expected) {
+                return Err(self.unexpected());
+            }
+            self.index += 1;
+        }
+        Ok(())
+    }
+
+    // The position just past the input, where the parser reports running out
+    // of tokens.
+    pub(crate) fn end(&self) -> usize {
+        self.bytes.len()
+    }
+
+    // The position of the next token, so just past the last one lexed.
+    pub(crate) fn index(&self) -> usize {
+        self.index
+    }
+
+    fn starts_with(&self, index: usize, prefix: &str) -> bool {
+        self.bytes[index..].starts_with(prefix.as_bytes())
+    }
+
+    /// Starts lexing at byte `index` instead, so positions are reported in all
+    /// of the text while only the part after `index` is lexed.
+    pub fn starting_at(mut self, index: usize) -> Self {
+        self.index = index;
+        self
+    }
+
+    pub fn with_whitespace(mut self, whitespace: bool) -> Self {
+        self.whitespace = whitespace;
+        self
+    }
+
+    /// Report a standalone letter that is a unit only in the other case, like
+    /// `D`, as [`Error::WrongCaseUnit`] with the suggested unit. As both `m`
+    /// and `M` are units, these are never reported, and neither is `H` unless
+    /// it follows a value, as `/H` floors to the half hour.
+    pub fn with_strict_case(mut self, strict_case: bool) -> Self {
+        self.strict_case = strict_case;
+        self
+    }
+
+    /// Skip a `#` and everything after it as a comment, like in
+    /// `now+1d # tomorrow`.
+    pub fn with_comments(mut self, comments: bool) -> Self {
+        self.comments = comments;
+        self
+    }
 }
 
 impl Iterator for Lexer<'_> {
     type Item = Result<(usize, Token)>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let (index, c) = self.chars.next()?;
+        let index = self.index;
+        let Some(c) = self.peek_char() else {
+            self.bytes.get(index)?;
+            bail!(self.unexpected());
+        };
+        self.index += c.len_utf8();
         Some(Ok((
             index,
             match c {
                 '0'..='9' => {
-                    let mut index_end = index + 1;
-                    while matches!(self.chars.peek(), Some((_, '0'..='9'))) {
-                        index_end += 1;
-                        self.chars.next();
+                    let mut number = Some(u32::from(c) - u32::from('0'));
+                    while let Some(digit @ b'0'..=b'9') = self.bytes.get(self.index) {
+                        number = number
+                            .and_then(|number| number.checked_mul(10))
+                            .and_then(|number| number.checked_add(u32::from(digit - b'0')));
+                        self.index += 1;
                     }
-                    match self.text[index..index_end].parse::<u32>() {
-                        Ok(number) => Token::Value(number),
-                        Err(err) => bail!(Error::InvalidNumber(
-                            self.text[index..index_end].to_string(),
-                            err
-                        )),
+                    // only digits were consumed, so overflow is the only failure
+                    match number {
+                        Some(number) => Token::Value(number),
+                        None => bail!(Error::InvalidNumber(index, self.index)),
                     }
                 }
-                'n' => {
-                    match self.chars.next() {
-                        Some((_, 'o')) => {}
-                        Some((index, c)) => bail!(Error::UnexpectedCharacter(index, c)),
-                        None => bail!(Error::UnexpectedCharacter(index + 1, '\u{3}')), // 3 is EOT
+                'n' if self.starts_with(index, "ne") => {
+                    if let Err(err) = self.keyword("next") {
+                        bail!(err);
                     }
-                    match self.chars.next() {
-                        Some((_, 'w')) => {}
-                        Some((index, c)) => bail!(Error::UnexpectedCharacter(index, c)),
-                        None => bail!(Error::UnexpectedCharacter(index + 1, '\u{3}')), // 3 is EOT
+                    Token::Next
+                }
+                'n' => {
+                    if let Err(err) = self.keyword("now") {
+                        bail!(err);
                     }
                     Token::Now
                 }
+                'e' => {
+                    if let Err(err) = self.keyword("epoch") {
+                        bail!(err);
+                    }
+                    Token::Epoch
+                }
+                'a' => {
+                    if let Err(err) = self.keyword("ago") {
+                        bail!(err);
+                    }
+                    Token::Ago
+                }
+                'l' => {
+                    if let Err(err) = self.keyword("last") {
+                        bail!(err);
+                    }
+                    Token::Last
+                }
+                't' if self.starts_with(index, "thi") => {
+                    if let Err(err) = self.keyword("this") {
+                        bail!(err);
+                    }
+                    Token::This
+                }
+                't' if self.starts_with(index, "tu") => {
+                    if let Err(err) = self.keyword("tue") {
+                        bail!(err);
+                    }
+                    Token::Weekday(Weekday::Tuesday)
+                }
+                't' => {
+                    if let Err(err) = self.keyword("thu") {
+                        bail!(err);
+                    }
+                    Token::Weekday(Weekday::Thursday)
+                }
+                'b' => {
+                    if let Err(err) = self.keyword("bh") {
+                        bail!(err);
+                    }
+                    Token::BusinessHours
+                }
+                'f' => {
+                    if let Err(err) = self.keyword("fri") {
+                        bail!(err);
+                    }
+                    Token::Weekday(Weekday::Friday)
+                }
+                // weekdays share their first letter with the units, so only
+                // the second letter tells them apart
+                'm' if self.starts_with(index, "mon") => {
+                    self.index += 2;
+                    Token::Weekday(Weekday::Monday)
+                }
+                // `mo` is an alias of `M`, while `m` alone is a minute
+                'm' if self.starts_with(index, "mo") => {
+                    self.index += 1;
+                    Token::Month
+                }
+                'm' if self.starts_with(index, "ms") => {
+                    self.index += 1;
+                    Token::Millisecond
+                }
+                'w' if self.starts_with(index, "we") => {
+                    if let Err(err) = self.keyword("wed") {
+                        bail!(err);
+                    }
+                    Token::Weekday(Weekday::Wednesday)
+                }
+                's' if self.starts_with(index, "sa") => {
+                    if let Err(err) = self.keyword("sat") {
+                        bail!(err);
+                    }
+                    Token::Weekday(Weekday::Saturday)
+                }
+                's' if self.starts_with(index, "su") => {
+                    if let Err(err) = self.keyword("sun") {
+                        bail!(err);
+                    }
+                    Token::Weekday(Weekday::Sunday)
+                }
                 '/' => Token::Floor,
+                '|' => Token::AssertFloor,
                 '+' => Token::Add,
                 '-' => Token::Sub,
+                '@' => Token::At,
+                '*' => Token::Repeat,
+                '~' => Token::Jitter,
                 'y' => Token::Year,
+                'Y' => Token::IsoYear,
                 'M' => Token::Month,
                 'w' => Token::Week,
                 'd' => Token::Day,
                 'h' => Token::Hour,
+                'H' if self.strict_case
+                    && index
+                        .checked_sub(1)
+                        .and_then(|before| self.bytes.get(before))
+                        .is_some_and(u8::is_ascii_digit)
+                    && !self.peek_char().is_some_and(char::is_alphabetic) =>
+                {
+                    bail!(Error::WrongCaseUnit(index, 'h'))
+                }
+                'H' => Token::HalfHour,
+                'q' => Token::QuarterHour,
                 'm' => Token::Minute,
                 's' => Token::Second,
+                'W' | 'D' | 'S'
+                    if self.strict_case && !self.peek_char().is_some_and(char::is_alphabetic) =>
+                {
+                    bail!(Error::WrongCaseUnit(index, c.to_ascii_lowercase()))
+                }
+                '#' if self.comments => {
+                    self.index = self.bytes.len();
+                    return None;
+                }
+                c if c.is_whitespace() && self.whitespace => {
+                    while let Some(c) = self.peek_char() {
+                        if !c.is_whitespace() {
+                            break;
+                        }
+                        self.index += c.len_utf8();
+                    }
+                    Token::Whitespace(self.index - index)
+                }
                 c if c.is_whitespace() => return self.next(),
                 c => bail!(Error::UnexpectedCharacter(index, c)),
             },
@@ -159,7 +451,7 @@ mod test {
 
     #[test]
     fn large_number_error() {
-        parse_matches!("now+4294967297y", Err(Error::InvalidNumber(_, _)));
+        parse_eq!("now+4294967297y", Err(Error::InvalidNumber(4, 14)));
     }
 
     #[test]
@@ -180,6 +472,166 @@ mod test {
         );
     }
 
+    #[test]
+    fn epoch() {
+        parse_eq!(
+            "epoch+1d",
+            Ok(vec![
+                (0, Token::Epoch),
+                (5, Token::Add),
+                (6, Token::Value(1)),
+                (7, Token::Day)
+            ])
+        );
+        parse_eq!("epic", Err(Error::UnexpectedCharacter(2, 'i')));
+    }
+
+    #[test]
+    fn ago() {
+        parse_eq!(
+            "now 3d ago",
+            Ok(vec![
+                (0, Token::Now),
+                (4, Token::Value(3)),
+                (5, Token::Day),
+                (7, Token::Ago),
+            ])
+        );
+    }
+
+    #[test]
+    fn weekdays() {
+        parse_eq!(
+            "now next mon",
+            Ok(vec![
+                (0, Token::Now),
+                (4, Token::Next),
+                (9, Token::Weekday(Weekday::Monday)),
+            ])
+        );
+        parse_eq!(
+            "last tue this wed thu fri sat sun",
+            Ok(vec![
+                (0, Token::Last),
+                (5, Token::Weekday(Weekday::Tuesday)),
+                (9, Token::This),
+                (14, Token::Weekday(Weekday::Wednesday)),
+                (18, Token::Weekday(Weekday::Thursday)),
+                (22, Token::Weekday(Weekday::Friday)),
+                (26, Token::Weekday(Weekday::Saturday)),
+                (30, Token::Weekday(Weekday::Sunday)),
+            ])
+        );
+    }
+
+    #[test]
+    fn at() {
+        parse_eq!(
+            "1w@fri",
+            Ok(vec![
+                (0, Token::Value(1)),
+                (1, Token::Week),
+                (2, Token::At),
+                (3, Token::Weekday(Weekday::Friday)),
+            ])
+        );
+    }
+
+    #[test]
+    fn weekdays_next_to_units() {
+        parse_eq!(
+            "5m mon 1w wed 2s sun",
+            Ok(vec![
+                (0, Token::Value(5)),
+                (1, Token::Minute),
+                (3, Token::Weekday(Weekday::Monday)),
+                (7, Token::Value(1)),
+                (8, Token::Week),
+                (10, Token::Weekday(Weekday::Wednesday)),
+                (14, Token::Value(2)),
+                (15, Token::Second),
+                (17, Token::Weekday(Weekday::Sunday)),
+            ])
+        );
+        parse_eq!("now tu", Err(Error::UnexpectedCharacter(6, '\u{3}')));
+        parse_eq!("now nex", Err(Error::UnexpectedCharacter(7, '\u{3}')));
+        parse_eq!("tha", Err(Error::UnexpectedCharacter(2, 'a')));
+    }
+
+    #[test]
+    fn incomplete_keyword() {
+        parse_eq!("no", Err(Error::UnexpectedCharacter(2, '\u{3}')));
+        parse_eq!("now+1d ag0", Err(Error::UnexpectedCharacter(9, '0')));
+    }
+
+    #[test]
+    fn whitespace() {
+        let input = " now\t+ 1d  /\u{a0}d ";
+        let tokens = Lexer::new(input)
+            .with_whitespace(true)
+            .collect::<Result<Vec<_>, _>>()
+            .expect("input should lex");
+
+        assert_eq!(
+            tokens,
+            vec![
+                (0, Token::Whitespace(1)),
+                (1, Token::Now),
+                (4, Token::Whitespace(1)),
+                (5, Token::Add),
+                (6, Token::Whitespace(1)),
+                (7, Token::Value(1)),
+                (8, Token::Day),
+                (9, Token::Whitespace(2)),
+                (11, Token::Floor),
+                (12, Token::Whitespace(2)),
+                (14, Token::Day),
+                (15, Token::Whitespace(1)),
+            ]
+        );
+
+        let rendered = tokens
+            .iter()
+            .map(|(index, token)| match token {
+                Token::Whitespace(len) => input[*index..index + len].to_string(),
+                token => token.to_string(),
+            })
+            .collect::<String>();
+        assert_eq!(rendered, input);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Token::Now.to_string(), "now");
+        assert_eq!(Token::Value(15).to_string(), "15");
+        assert_eq!(Token::Add.to_string(), "+");
+        assert_eq!(Token::Sub.to_string(), "-");
+        assert_eq!(Token::Floor.to_string(), "/");
+        assert_eq!(Token::Ago.to_string(), "ago");
+        assert_eq!(Token::Year.to_string(), "y");
+        assert_eq!(Token::Month.to_string(), "M");
+        assert_eq!(Token::Week.to_string(), "w");
+        assert_eq!(Token::Day.to_string(), "d");
+        assert_eq!(Token::Hour.to_string(), "h");
+        assert_eq!(Token::Minute.to_string(), "m");
+        assert_eq!(Token::Second.to_string(), "s");
+        assert_eq!(Token::This.to_string(), "this");
+        assert_eq!(Token::Next.to_string(), "next");
+        assert_eq!(Token::Last.to_string(), "last");
+        assert_eq!(Token::Weekday(Weekday::Thursday).to_string(), "thu");
+        assert_eq!(Token::Whitespace(3).to_string(), "   ");
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let input = "now+1y-15M/w 3d ago";
+        let rendered = Lexer::new(input)
+            .with_whitespace(true)
+            .map(|token| token.map(|(_, token)| token.to_string()))
+            .collect::<Result<String, _>>();
+        assert_eq!(rendered, Ok(input.to_string()));
+    }
+
     #[test]
     fn cursed() {
         // The tokenizer aggressively doesn't care about the structure of the input (as
@@ -202,4 +654,88 @@ mod test {
             ])
         );
     }
+
+    #[test]
+    fn month_alias() {
+        parse_eq!(
+            "1mo+1m",
+            Ok(vec![
+                (0, Token::Value(1)),
+                (1, Token::Month),
+                (3, Token::Add),
+                (4, Token::Value(1)),
+                (5, Token::Minute),
+            ])
+        );
+        parse_eq!(
+            "1m mon",
+            Ok(vec![
+                (0, Token::Value(1)),
+                (1, Token::Minute),
+                (3, Token::Weekday(Weekday::Monday)),
+            ])
+        );
+    }
+
+    #[test]
+    fn millisecond() {
+        parse_eq!(
+            "now-5ms",
+            Ok(vec![
+                (0, Token::Now),
+                (3, Token::Sub),
+                (4, Token::Value(5)),
+                (5, Token::Millisecond),
+            ])
+        );
+        parse_eq!(
+            "now/m",
+            Ok(vec![(0, Token::Now), (3, Token::Floor), (4, Token::Minute)])
+        );
+    }
+
+    #[test]
+    fn strict_case() {
+        let lex = |input| {
+            Lexer::new(input)
+                .with_strict_case(true)
+                .collect::<Result<Vec<_>, _>>()
+        };
+        assert_eq!(lex("now+1D"), Err(Error::WrongCaseUnit(5, 'd')));
+        assert_eq!(lex("now-2H ago"), Err(Error::WrongCaseUnit(5, 'h')));
+        assert_eq!(lex("now-2S ago"), Err(Error::WrongCaseUnit(5, 's')));
+        assert_eq!(lex("now/H"), parse("now/H"));
+        assert_eq!(lex("now/W"), Err(Error::WrongCaseUnit(4, 'w')));
+        assert_eq!(lex("now/Y"), parse("now/Y"));
+        assert_eq!(lex("now+1Days"), Err(Error::UnexpectedCharacter(5, 'D')));
+        assert_eq!(lex("now+1m+1M"), parse("now+1m+1M"));
+        parse_eq!("now+1D", Err(Error::UnexpectedCharacter(5, 'D')));
+    }
+
+    #[test]
+    fn comments() {
+        let lex = |input| {
+            Lexer::new(input)
+                .with_comments(true)
+                .collect::<Result<Vec<_>, _>>()
+        };
+        assert_eq!(lex("now+1d # tomorrow"), parse("now+1d"));
+        assert_eq!(lex("now/d#+1d"), parse("now/d"));
+        assert_eq!(lex("# now"), Ok(vec![]));
+        assert_eq!(lex("now # ä"), parse("now"));
+        parse_eq!("now+1d # tomorrow", Err(Error::UnexpectedCharacter(7, '#')));
+    }
+
+    #[test]
+    fn bytes() {
+        let lex = |bytes: &[u8]| Lexer::from_bytes(bytes).collect::<Result<Vec<_>, _>>();
+        assert_eq!(lex(b"now\t+ 1d/d"), parse("now\t+ 1d/d"));
+        assert_eq!(
+            lex(b"now+1d \xc2\xa0/d"),
+            Err(Error::UnexpectedByte(7, 0xc2)),
+            "non-ASCII whitespace is not decoded"
+        );
+        assert_eq!(lex(b"no\xff"), Err(Error::UnexpectedByte(2, 0xff)));
+        assert_eq!(lex(b"nov"), Err(Error::UnexpectedCharacter(2, 'v')));
+    }
 }
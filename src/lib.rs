@@ -1,10 +1,35 @@
+pub mod components;
 pub mod error;
 pub mod impls;
 pub mod lexer;
+pub mod macros;
 pub mod parser;
+pub mod prelude;
 
-pub use error::{Error, Result};
-use parser::{Expression, Parser, Unit};
+// only used by the integration tests
+#[cfg(test)]
+use trybuild as _;
+// only used with the serde feature
+#[cfg(all(test, not(feature = "serde")))]
+use serde_json as _;
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    hash::{BuildHasher, Hasher},
+};
+
+pub use components::TimeComponents;
+pub use error::{Error, ErrorKind, ResolveError, Result};
+#[cfg(feature = "chrono")]
+pub use impls::chrono::{
+    apply_to_rfc3339, boundaries_between, duration_until_next, duration_until_next_day,
+    duration_until_next_hour, duration_until_next_week, humanize, parse_str_in_zone,
+    parse_str_signed_delta, resolve_in_text, to_iso8601_offset,
+};
+#[cfg(feature = "chrono")]
+pub use impls::chrono_zoned::ZonedDate;
+use lexer::Lexer;
+use parser::{Expression, Parser, Qualifier, Unit, Weekday};
 
 pub const MONTHS_PER_YEAR: u32 = 12;
 pub const DAYS_PER_WEEK: u32 = 7;
@@ -14,44 +39,77 @@ pub const SECONDS_PER_MINUTE: u32 = 60;
 
 pub trait RelativeTimeNow {
     fn now() -> Self;
+
+    /// The current time floored to `unit`, e.g. to whole seconds, so the noise
+    /// of a precise clock does not get in the way of comparing results.
+    fn now_truncated(unit: Unit) -> Result<Self>
+    where
+        Self: RelativeTime,
+    {
+        Self::now().floor_checked(unit).map(|(now, _)| now)
+    }
 }
 
-pub trait RelativeTime: Clone {
+pub trait RelativeTime: Clone + PartialEq {
     fn add_years(self, value: u32) -> Result<Self> {
-        self.add_months(value * MONTHS_PER_YEAR)
+        self.add_months(scale(value, MONTHS_PER_YEAR)?)
     }
     fn add_months(self, value: u32) -> Result<Self>;
     fn add_weeks(self, value: u32) -> Result<Self> {
-        self.add_days(value * DAYS_PER_WEEK)
+        self.add_days(scale(value, DAYS_PER_WEEK)?)
     }
     fn add_days(self, value: u32) -> Result<Self> {
-        self.add_hours(value * HOURS_PER_DAY)
+        self.add_hours(scale(value, HOURS_PER_DAY)?)
     }
     fn add_hours(self, value: u32) -> Result<Self> {
-        self.add_minutes(value * MINUTES_PER_HOUR)
+        self.add_minutes(scale(value, MINUTES_PER_HOUR)?)
     }
     fn add_minutes(self, value: u32) -> Result<Self> {
-        self.add_seconds(value * SECONDS_PER_MINUTE)
+        self.add_seconds(scale(value, SECONDS_PER_MINUTE)?)
     }
     fn add_seconds(self, value: u32) -> Result<Self>;
 
     fn sub_years(self, value: u32) -> Result<Self> {
-        self.sub_months(value * MONTHS_PER_YEAR)
+        self.sub_months(scale(value, MONTHS_PER_YEAR)?)
     }
     fn sub_months(self, value: u32) -> Result<Self>;
     fn sub_weeks(self, value: u32) -> Result<Self> {
-        self.sub_days(value * DAYS_PER_WEEK)
+        self.sub_days(scale(value, DAYS_PER_WEEK)?)
     }
     fn sub_days(self, value: u32) -> Result<Self> {
-        self.sub_hours(value * HOURS_PER_DAY)
+        self.sub_hours(scale(value, HOURS_PER_DAY)?)
     }
     fn sub_hours(self, value: u32) -> Result<Self> {
-        self.sub_minutes(value * MINUTES_PER_HOUR)
+        self.sub_minutes(scale(value, MINUTES_PER_HOUR)?)
     }
     fn sub_minutes(self, value: u32) -> Result<Self> {
-        self.sub_seconds(value * SECONDS_PER_MINUTE)
+        self.sub_seconds(scale(value, SECONDS_PER_MINUTE)?)
     }
     fn sub_seconds(self, value: u32) -> Result<Self>;
+    /// Subtracts nanoseconds, for the `end_of_*` methods. Types without
+    /// nanoseconds result in [`Error::UnsupportedNanos`].
+    fn sub_nanos(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedNanos)
+    }
+    /// Adds nanoseconds, for [`RelativeTime::add_components`]. Types without
+    /// nanoseconds result in [`Error::UnsupportedNanos`].
+    fn add_nanos(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedNanos)
+    }
+    /// Adds milliseconds, which only types with sub-second precision support.
+    fn add_millis(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Millisecond))
+    }
+    fn sub_millis(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Millisecond))
+    }
+    /// Adds microseconds, which only types with sub-second precision support.
+    fn add_micros(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Microsecond))
+    }
+    fn sub_micros(self, _value: u32) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Microsecond))
+    }
 
     fn floor_years(self) -> Result<Self>;
     fn floor_months(self) -> Result<Self>;
@@ -60,6 +118,154 @@ pub trait RelativeTime: Clone {
     fn floor_hours(self) -> Result<Self>;
     fn floor_minutes(self) -> Result<Self>;
     fn floor_seconds(self) -> Result<Self>;
+    /// Zeroes the precision below the millisecond, like `now/ms`.
+    fn floor_millis(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Millisecond))
+    }
+    fn floor_micros(self) -> Result<Self> {
+        Err(Error::UnsupportedUnit(Unit::Microsecond))
+    }
+
+    /// The day of the week, used to resolve weekday terms such as `next mon`.
+    /// Types without a notion of weekdays return `None`.
+    fn day_of_week(&self) -> Option<Weekday> {
+        None
+    }
+
+    /// The day of the month, starting at 1, used to let days past the end of a
+    /// month spill into the next one. Types without days return `None`.
+    fn day_of_month(&self) -> Option<u32> {
+        None
+    }
+
+    /// The unix epoch, in the time zone of `self` if any, used to resolve the
+    /// `epoch` keyword. Types without an epoch return `None`.
+    fn epoch(&self) -> Option<Self> {
+        None
+    }
+
+    /// Floors to the start of the ISO 8601 week-numbering year, the monday of
+    /// its first week, which may be in the previous december.
+    fn floor_iso_years(self) -> Result<Self> {
+        Err(Error::UnsupportedExpression(Expression::FloorIsoYear))
+    }
+
+    /// Floors to business hours on weekdays, for `/bh`: to the opening time of
+    /// today during business hours, and otherwise to the closing time of the
+    /// most recent business day.
+    fn floor_business_hours(self, _hours: BusinessHours) -> Result<Self> {
+        Err(Error::UnsupportedExpression(Expression::FloorBusinessHours))
+    }
+
+    /// The local wall clock of `self` if it occurs twice in its time zone, as
+    /// when the clocks are turned back, for
+    /// `ParseOptions::reject_ambiguous_local_time`. Types without a time zone
+    /// return `None`.
+    fn ambiguous_local_time(&self) -> Option<TimeComponents> {
+        None
+    }
+
+    /// Floors to a multiple of `minutes` within the hour, e.g. 05:40 to 05:30
+    /// for 15 minutes with `/q`.
+    fn floor_minutes_by(self, minutes: u32) -> Result<Self> {
+        Err(Error::UnsupportedExpression(Expression::FloorMinutes(
+            minutes,
+        )))
+    }
+
+    /// Floors to the start of weekday `n` of the month, counting from 1, e.g.
+    /// the third thursday for `/3thu`. A month without it, like one with only
+    /// four fridays, results in [`Error::NoSuchWeekday`].
+    fn nth_weekday_of_month(self, n: u32, weekday: Weekday) -> Result<Self> {
+        Err(Error::UnsupportedExpression(Expression::NthWeekday(
+            n, weekday,
+        )))
+    }
+
+    /// Floors to the start of the most recent `weekday`, which is today if it
+    /// is that weekday. Unlike [`RelativeTime::floor_weeks`] this does not
+    /// depend on where a week starts.
+    fn floor_weekday(self, weekday: Weekday) -> Result<Self> {
+        let current =
+            self.day_of_week()
+                .ok_or(Error::UnsupportedExpression(Expression::FloorWeekday(
+                    weekday,
+                )))?;
+        let days = (current.num_days_from_monday() + DAYS_PER_WEEK
+            - weekday.num_days_from_monday())
+            % DAYS_PER_WEEK;
+        self.sub_days(days)?.floor_days()
+    }
+
+    /// Floors to `unit`, also reporting whether that moved the value, which is
+    /// what exclusive floors need. Types that can tell without comparing the
+    /// result can override this.
+    fn floor_checked(self, unit: Unit) -> Result<(Self, bool)> {
+        let floored = match unit {
+            Unit::Year => self.clone().floor_years(),
+            Unit::Month => self.clone().floor_months(),
+            Unit::Week => self.clone().floor_weeks(),
+            Unit::Day => self.clone().floor_days(),
+            Unit::Hour => self.clone().floor_hours(),
+            Unit::Minute => self.clone().floor_minutes(),
+            Unit::Second => self.clone().floor_seconds(),
+            Unit::Millisecond => self.clone().floor_millis(),
+            Unit::Microsecond => self.clone().floor_micros(),
+        }?;
+        let moved = floored != self;
+        Ok((floored, moved))
+    }
+
+    /// Applies all of `delta` in one call, from years down to nanoseconds,
+    /// adding the positive components and subtracting the negative ones, e.g.
+    /// the delta of [`parse_str_delta`]. Components that are zero are skipped,
+    /// and one that does not fit in a `u32` results in [`Error::InvalidDelta`].
+    fn add_components(self, delta: &TimeComponents) -> Result<Self> {
+        type Step<T> = fn(T, u32) -> Result<T>;
+        let steps: [(i64, Step<Self>, Step<Self>); 7] = [
+            (delta.years, Self::add_years, Self::sub_years),
+            (delta.months, Self::add_months, Self::sub_months),
+            (delta.days, Self::add_days, Self::sub_days),
+            (delta.hours, Self::add_hours, Self::sub_hours),
+            (delta.minutes, Self::add_minutes, Self::sub_minutes),
+            (delta.seconds, Self::add_seconds, Self::sub_seconds),
+            (delta.nanos, Self::add_nanos, Self::sub_nanos),
+        ];
+        steps.into_iter().try_fold(self, |time, (value, add, sub)| {
+            let magnitude =
+                u32::try_from(value.unsigned_abs()).map_err(|_err| Error::InvalidDelta)?;
+            match value.cmp(&0) {
+                std::cmp::Ordering::Less => sub(time, magnitude),
+                std::cmp::Ordering::Equal => Ok(time),
+                std::cmp::Ordering::Greater => add(time, magnitude),
+            }
+        })
+    }
+
+    /// The last nanosecond of the day, just before the next one starts. Unlike
+    /// rounding up, this also moves values at the start of the day.
+    fn end_of_day(self) -> Result<Self> {
+        self.floor_days()?.add_days(1)?.sub_nanos(1)
+    }
+
+    /// The last nanosecond of the week, the sunday before the next monday.
+    fn end_of_week(self) -> Result<Self> {
+        self.floor_weeks()?.add_weeks(1)?.sub_nanos(1)
+    }
+
+    /// The last nanosecond of the month, on the last day of the month.
+    fn end_of_month(self) -> Result<Self> {
+        self.floor_months()?.add_months(1)?.sub_nanos(1)
+    }
+
+    /// The last nanosecond of the year, on the 31st of december.
+    fn end_of_year(self) -> Result<Self> {
+        self.floor_years()?.add_years(1)?.sub_nanos(1)
+    }
+}
+
+fn scale(value: u32, factor: u32) -> Result<u32> {
+    value.checked_mul(factor).ok_or(Error::InvalidDelta)
 }
 
 pub fn parse_str<T: RelativeTime + RelativeTimeNow>(text: &str) -> Result<T> {
@@ -68,68 +274,1085 @@ pub fn parse_str<T: RelativeTime + RelativeTimeNow>(text: &str) -> Result<T> {
     parse_str_with_now(text, T::now())
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Reject any expression before `now`, e.g. `+1s+now`.
+    pub require_leading_now: bool,
+    /// Move a value that is already on the boundary of the unit back by one
+    /// unit when flooring, e.g. `now/d` at midnight yields the previous day.
+    pub floor_exclusive: bool,
+    /// What to do with days past the end of the month after adding months.
+    pub month_overflow: MonthOverflow,
+    /// Keep to the last day of the month when adding years or months to the
+    /// last day of a month, e.g. `2023-02-28` plus a month is `2023-03-31`
+    /// rather than `2023-03-28`. Other days follow `month_overflow`.
+    pub month_add_sticky_eom: bool,
+    /// Floor to the first of the month instead when flooring to the week would
+    /// move into the previous month, e.g. `now/w` on Wednesday `2023-11-01`
+    /// yields `2023-11-01` rather than Monday `2023-10-30`.
+    pub week_floor_clamp_to_month: bool,
+    /// Where the weeks `now/w` floors to start.
+    pub week_mode: WeekMode,
+    /// Report units in the wrong case, like `now+1D`, as
+    /// [`Error::WrongCaseUnit`] suggesting the right one.
+    pub strict_case: bool,
+    /// Sum the terms between floors and weekdays per unit, and apply each sum
+    /// once from the largest unit to the smallest, so only the net offset has
+    /// to be in range, e.g. `now+1000000000d-999999999d`. Terms are no longer
+    /// applied in the order they are written.
+    pub net_apply: bool,
+    /// Reject any single term larger than the given amount of the unit, e.g.
+    /// `Some((10, Unit::Year))` rejects `now+11y` and `now-4000d`. Calendar
+    /// units are compared using the longest year of 366 days, of which a month
+    /// is a twelfth.
+    pub max_magnitude: Option<(u32, Unit)>,
+    /// Floor the result to the given unit as a last step, e.g. to
+    /// `Unit::Microsecond` for databases that store microseconds, so all
+    /// results are aligned the same way. Unlike a floor in the expression this
+    /// ignores `floor_exclusive` and `week_mode`.
+    pub truncate_to: Option<Unit>,
+    /// Reject floors to units smaller than a day, like `now/h`, as
+    /// [`Error::UnsupportedFloor`], for callers that only use the date.
+    pub reject_subday_floor: bool,
+    /// The opening and closing time of business days, Monday to Friday, which
+    /// `now/bh` floors to. Without them `/bh` is an
+    /// [`Error::UnsupportedExpression`].
+    pub business_hours: Option<BusinessHours>,
+    /// Report a floor or a calendar unit that lands on a wall clock that
+    /// occurs twice, when the clocks are turned back, as
+    /// [`Error::AmbiguousLocalTime`]. Otherwise floors take the occurrence
+    /// that is not past the time being floored, and calendar units the
+    /// earliest. Hours and smaller units are exact, so they are never
+    /// ambiguous.
+    pub reject_ambiguous_local_time: bool,
+    /// Skip a `#` and everything after it, e.g. `now+1d # tomorrow` is
+    /// `now+1d`, otherwise a `#` is an [`Error::UnexpectedCharacter`].
+    pub allow_comments: bool,
+    /// Seed the jitter of `~` terms, so the same text and seed always resolve
+    /// to the same time, e.g. the id of a job to spread out its runs.
+    /// Otherwise every resolve jitters differently.
+    pub jitter_seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MonthOverflow {
+    /// Clamp to the last day of the month, e.g. `2023-01-31` plus a month is
+    /// `2023-02-28`.
+    #[default]
+    Clamp,
+    /// Move the remaining days into the next month, e.g. `2023-01-31` plus a
+    /// month is `2023-03-03`.
+    Spill,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WeekMode {
+    /// Weeks of seven days counted from the unix epoch, so starting on
+    /// Thursday like `1970-01-01`.
+    EpochAligned,
+    /// Weeks starting on Monday, as in ISO 8601.
+    #[default]
+    IsoMonday,
+    /// Weeks starting on Sunday, as in most calendars in the US.
+    CalendarSunday,
+    /// Weeks of seven days counted from the first of January, so the last week
+    /// of a year is cut short by the next one.
+    YearRelative,
+}
+
+/// The opening and closing time of business days, each as an hour and a
+/// minute on the local wall clock, see [`ParseOptions::business_hours`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusinessHours {
+    open: (u32, u32),
+    close: (u32, u32),
+}
+
+impl BusinessHours {
+    /// Business hours from `open` to `close`, e.g. `((9, 0), (17, 30))`. A time
+    /// that is not on the clock, or an `open` that is not before `close`, is an
+    /// [`Error::InvalidBusinessHours`].
+    pub fn new(open: (u32, u32), close: (u32, u32)) -> Result<Self> {
+        let on_clock = |(hour, minute)| hour < HOURS_PER_DAY && minute < MINUTES_PER_HOUR;
+        if !on_clock(open) || !on_clock(close) || open >= close {
+            return Err(Error::InvalidBusinessHours);
+        }
+        Ok(Self { open, close })
+    }
+
+    /// The hour and minute business days open.
+    pub const fn open(self) -> (u32, u32) {
+        self.open
+    }
+
+    /// The hour and minute business days close.
+    pub const fn close(self) -> (u32, u32) {
+        self.close
+    }
+}
+
+/// Resolves `text` relative to `now`.
+///
+/// Expressions are evaluated strictly from left to right, there is no
+/// operator precedence and nothing is reordered. Floors act as barriers, so
+/// `now+1M/M+14d` is the 15th of next month, while `now+14d/M` is the start of
+/// the month two weeks from now.
+///
+/// This never panics, any `text` results in either a time or an [`Error`],
+/// as long as the [`RelativeTime`] implementation of `T` does not panic.
 pub fn parse_str_with_now<T: RelativeTime>(text: &str, now: T) -> Result<T> {
-    if text.trim().trim_start_matches('+').trim_start() == "now" {
+    parse_str_with_options(text, now, ParseOptions::default())
+}
+
+/// Like [`parse_str_with_now`], but resolves `default` instead when `text` is
+/// `None` or blank, as for a setting that may be unset, e.g.
+/// `parse_str_or_default(std::env::var("SINCE").ok().as_deref(), "now-1d", now)`.
+pub fn parse_str_or_default<T: RelativeTime>(
+    text: Option<&str>,
+    default: &str,
+    now: T,
+) -> Result<T> {
+    let text = text
+        .filter(|text| !text.trim().is_empty())
+        .unwrap_or(default);
+    parse_str_with_now(text, now)
+}
+
+pub fn parse_str_with_options<T: RelativeTime>(
+    text: &str,
+    now: T,
+    options: ParseOptions,
+) -> Result<T> {
+    if options.truncate_to.is_none() && text.trim().trim_start_matches('+').trim_start() == "now" {
         // shortcut so we don't have to any more logic
         return Ok(now);
     }
 
-    let mut parser = Parser::new(text);
+    let lexer = Lexer::new(text)
+        .with_strict_case(options.strict_case)
+        .with_comments(options.allow_comments);
+    evaluate(spanned(Parser::from_lexer(lexer)), now, options)
+}
+
+/// Like [`parse_str_with_now`], but the error also tells whether lexing,
+/// parsing or resolving failed, and which part of `text` it is about, e.g. to
+/// underline it in a UI.
+pub fn resolve_checked<T: RelativeTime>(text: &str, now: T) -> Result<T, ResolveError> {
+    parse_str_with_now(text, now).map_err(|error| ResolveError::new(text, error))
+}
+
+/// Like [`parse_str`], but with the current time floored to `unit` first, see
+/// [`RelativeTimeNow::now_truncated`]. [`parse_str`] itself keeps the full
+/// precision of the clock.
+pub fn parse_str_truncated<T: RelativeTime + RelativeTimeNow>(text: &str, unit: Unit) -> Result<T> {
+    parse_str_with_now(text, T::now_truncated(unit)?)
+}
+
+/// Like [`parse_str`], but for ASCII bytes, which are not validated as UTF-8
+/// first. Any other byte results in [`Error::UnexpectedByte`].
+pub fn parse_bytes<T: RelativeTime + RelativeTimeNow>(bytes: &[u8]) -> Result<T> {
+    parse_bytes_with_now(bytes, T::now())
+}
+
+/// Like [`parse_str_with_now`], but for ASCII bytes, see [`parse_bytes`].
+pub fn parse_bytes_with_now<T: RelativeTime>(bytes: &[u8], now: T) -> Result<T> {
+    evaluate(
+        spanned(Parser::from_bytes(bytes)),
+        now,
+        ParseOptions::default(),
+    )
+}
+
+/// Resolves expressions that were built rather than parsed, relative to
+/// `base`, e.g. `[Expression::Now(0), Expression::Add(1, Unit::Day)]` is the
+/// day after `base`. They are evaluated like the expressions of a text, so
+/// `now` has to occur once. Positions in errors are the index of the
+/// expression, except that [`Error::MultipleNow`] reports the position carried
+/// by the repeated [`Expression::Now`] or [`Expression::Epoch`], as given by the
+/// caller.
+pub fn resolve_exprs<T, I>(base: T, exprs: I) -> Result<T>
+where
+    T: RelativeTime,
+    I: IntoIterator<Item = Expression>,
+{
+    let exprs = exprs.into_iter().enumerate().map(Ok);
+    evaluate(exprs, base, ParseOptions::default())
+}
+
+fn spanned(mut parser: Parser<'_>) -> impl Iterator<Item = Result<(usize, Expression)>> + '_ {
+    std::iter::from_fn(move || parser.next_spanned())
+}
+
+// Whether `expr` floors the time, which is not allowed before `now`.
+const fn is_floor(expr: &Expression) -> bool {
+    matches!(
+        expr,
+        Expression::Floor(_)
+            | Expression::FloorWeekday(_)
+            | Expression::FloorIsoYear
+            | Expression::FloorBusinessHours
+            | Expression::AssertFloor(_)
+            | Expression::FloorMinutes(_)
+            | Expression::NthWeekday(..)
+    )
+}
 
+fn evaluate<T: RelativeTime>(
+    mut parser: impl Iterator<Item = Result<(usize, Expression)>>,
+    mut now: T,
+    options: ParseOptions,
+) -> Result<T> {
     let mut exprs = Vec::new();
 
     loop {
         match parser.next().transpose()? {
             None => return Err(Error::MissingNow),
-            Some(Expression::Now) => break,
-            Some(Expression::Floor(_)) => return Err(Error::FloorBeforeNow),
+            Some((_, Expression::Now(_))) => break,
+            Some((_, expr @ Expression::Epoch(_))) => {
+                now = now.epoch().ok_or(Error::UnsupportedExpression(expr))?;
+                break;
+            }
+            Some((_, expr)) if is_floor(&expr) => return Err(Error::FloorBeforeNow),
+            Some((index, _)) if options.require_leading_now => {
+                // the offending expression is always the first one in the text
+                return Err(Error::NowNotFirst(index));
+            }
             Some(expr) => exprs.push(expr),
         }
     }
 
-    let mut time = now;
-
-    for expr in exprs.into_iter().map(Ok).chain(parser) {
-        time = match expr? {
-            Expression::Now => Err(Error::MultipleNow),
-            Expression::Add(0, _) | Expression::Sub(0, _) => Ok(time),
-            Expression::Add(value, unit) => match unit {
-                Unit::Year => time.add_years(value),
-                Unit::Month => time.add_months(value),
-                Unit::Week => time.add_weeks(value),
-                Unit::Day => time.add_days(value),
-                Unit::Hour => time.add_hours(value),
-                Unit::Minute => time.add_minutes(value),
-                Unit::Second => time.add_seconds(value),
-            },
-            Expression::Sub(value, unit) => match unit {
-                Unit::Year => time.sub_years(value),
-                Unit::Month => time.sub_months(value),
-                Unit::Week => time.sub_weeks(value),
-                Unit::Day => time.sub_days(value),
-                Unit::Hour => time.sub_hours(value),
-                Unit::Minute => time.sub_minutes(value),
-                Unit::Second => time.sub_seconds(value),
-            },
-            Expression::Floor(unit) => match unit {
-                Unit::Year => time.floor_years(),
-                Unit::Month => time.floor_months(),
-                Unit::Week => time.floor_weeks(),
-                Unit::Day => time.floor_days(),
-                Unit::Hour => time.floor_hours(),
-                Unit::Minute => time.floor_minutes(),
-                Unit::Second => time.floor_seconds(),
-            },
-        }?
+    apply(now, exprs.into_iter().map(Ok).chain(parser), options)
+}
+
+// Applies the expressions after `now` to `time`.
+fn apply<T: RelativeTime>(
+    mut time: T,
+    exprs: impl Iterator<Item = Result<(usize, Expression)>>,
+    options: ParseOptions,
+) -> Result<T> {
+    // the position of the first term since the last other expression and the
+    // sum of them, per unit
+    let mut net = BTreeMap::new();
+    let mut jitter = None;
+
+    for expr in exprs {
+        let (index, expr) = expr?;
+        check_magnitude(index, expr, options.max_magnitude)?;
+        if options.net_apply {
+            if let Expression::Add(value, unit) | Expression::Sub(value, unit) = expr {
+                let value = i64::from(value);
+                let signed = if matches!(expr, Expression::Sub(..)) {
+                    -value
+                } else {
+                    value
+                };
+                net.entry(unit).or_insert((index, 0)).1 += signed;
+                continue;
+            }
+            time = apply_net(time, &mut net, options)?;
+        }
+        time = match expr {
+            Expression::Now(position) | Expression::Epoch(position) => {
+                Err(Error::MultipleNow(position))
+            }
+            Expression::Add(..) | Expression::Sub(..) => offset(time, expr, options),
+            Expression::Jitter(value, unit) => jitter
+                .get_or_insert_with(|| SplitMix::new(options.jitter_seed))
+                .offset(value, unit)
+                .and_then(|jittered| offset(time, jittered, options)),
+            Expression::Floor(Unit::Week) if options.week_floor_clamp_to_month => {
+                floor_week_in_month(&time, options)
+            }
+            Expression::Floor(unit) => floor(&time, unit, options),
+            Expression::Weekday(qualifier, weekday) => {
+                let current = time
+                    .day_of_week()
+                    .ok_or(Error::UnsupportedExpression(expr))?;
+                to_weekday(time, current, qualifier, weekday)
+            }
+            Expression::FloorWeekday(weekday) => {
+                time.clone().floor_weekday(weekday).and_then(|floored| {
+                    if options.floor_exclusive && floored == time {
+                        floored.sub_weeks(1)
+                    } else {
+                        Ok(floored)
+                    }
+                })
+            }
+            Expression::FloorBusinessHours => floor_business_hours(time, options),
+            Expression::FloorMinutes(minutes) => floor_minutes_by(&time, minutes, options),
+            Expression::NthWeekday(n, weekday) => time.nth_weekday_of_month(n, weekday),
+            Expression::AssertFloor(unit) => {
+                let options = ParseOptions {
+                    floor_exclusive: false,
+                    ..options
+                };
+                if floor(&time, unit, options)? == time {
+                    Ok(time)
+                } else {
+                    Err(Error::AlreadyPastBoundary(index, unit))
+                }
+            }
+            Expression::FloorIsoYear => time.clone().floor_iso_years().and_then(|floored| {
+                if options.floor_exclusive && floored == time {
+                    // ISO years differ in length, so floor the day before
+                    floored.sub_days(1).and_then(RelativeTime::floor_iso_years)
+                } else {
+                    Ok(floored)
+                }
+            }),
+        }
+        .map_err(|err| match err {
+            Error::InvalidTimestamp => Error::OverflowAt(index, expr),
+            err => err,
+        })?;
+        reject_ambiguous(&time, expr, options)?;
+    }
+
+    let time = apply_net(time, &mut net, options)?;
+    match options.truncate_to {
+        Some(unit) => time.floor_checked(unit).map(|(time, _)| time),
+        None => Ok(time),
+    }
+}
+
+// The SplitMix64 generator, which is plenty to spread out times. Without a
+// seed it starts from the random keys std gives every hasher.
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn new(seed: Option<u64>) -> Self {
+        Self(seed.unwrap_or_else(|| std::hash::RandomState::new().build_hasher().finish()))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // A term of a whole number of `unit`, from `value` back to `value` ahead.
+    fn offset(&mut self, value: u32, unit: Unit) -> Result<Expression> {
+        let value = u64::from(value);
+        let drawn = self.next() % (2 * value + 1);
+        let delta = u32::try_from(drawn.abs_diff(value)).map_err(|_err| Error::InvalidDelta)?;
+        Ok(if drawn < value {
+            Expression::Sub(delta, unit)
+        } else {
+            Expression::Add(delta, unit)
+        })
+    }
+}
+
+// Applies a single add or subtract term.
+fn offset<T: RelativeTime>(time: T, expr: Expression, options: ParseOptions) -> Result<T> {
+    match expr {
+        Expression::Add(0, _) | Expression::Sub(0, _) => Ok(time),
+        Expression::Add(_, Unit::Year | Unit::Month)
+        | Expression::Sub(_, Unit::Year | Unit::Month)
+            if options.month_add_sticky_eom && is_end_of_month(&time, expr)? =>
+        {
+            let options = ParseOptions {
+                month_add_sticky_eom: false,
+                month_overflow: MonthOverflow::Clamp,
+                ..options
+            };
+            to_end_of_month(offset(time, expr, options)?, expr)
+        }
+        Expression::Add(_, Unit::Year | Unit::Month)
+        | Expression::Sub(_, Unit::Year | Unit::Month)
+            if options.month_overflow == MonthOverflow::Spill =>
+        {
+            spill(time, expr)
+        }
+        Expression::Add(value, unit) => match unit {
+            Unit::Year => time.add_years(value),
+            Unit::Month => time.add_months(value),
+            Unit::Week => time.add_weeks(value),
+            Unit::Day => time.add_days(value),
+            Unit::Hour => time.add_hours(value),
+            Unit::Minute => time.add_minutes(value),
+            Unit::Second => time.add_seconds(value),
+            Unit::Millisecond => time.add_millis(value),
+            Unit::Microsecond => time.add_micros(value),
+        },
+        Expression::Sub(value, unit) => match unit {
+            Unit::Year => time.sub_years(value),
+            Unit::Month => time.sub_months(value),
+            Unit::Week => time.sub_weeks(value),
+            Unit::Day => time.sub_days(value),
+            Unit::Hour => time.sub_hours(value),
+            Unit::Minute => time.sub_minutes(value),
+            Unit::Second => time.sub_seconds(value),
+            Unit::Millisecond => time.sub_millis(value),
+            Unit::Microsecond => time.sub_micros(value),
+        },
+        _ => Ok(time),
     }
+}
 
+// Applies the summed terms once, from the largest unit to the smallest.
+fn apply_net<T: RelativeTime>(
+    mut time: T,
+    net: &mut BTreeMap<Unit, (usize, i64)>,
+    options: ParseOptions,
+) -> Result<T> {
+    for (unit, (index, value)) in std::mem::take(net) {
+        let magnitude = u32::try_from(value.unsigned_abs()).map_err(|_err| Error::InvalidDelta)?;
+        let expr = if value < 0 {
+            Expression::Sub(magnitude, unit)
+        } else {
+            Expression::Add(magnitude, unit)
+        };
+        // reported at the first term of the sum, like without summing
+        time = offset(time, expr, options).map_err(|err| match err {
+            Error::InvalidTimestamp => Error::OverflowAt(index, expr),
+            err => err,
+        })?;
+        reject_ambiguous(&time, expr, options)?;
+    }
     Ok(time)
 }
 
+// Checks `ParseOptions::reject_ambiguous_local_time` after `expr` moved the
+// wall clock to `time`.
+fn reject_ambiguous<T: RelativeTime>(
+    time: &T,
+    expr: Expression,
+    options: ParseOptions,
+) -> Result<()> {
+    if !options.reject_ambiguous_local_time {
+        return Ok(());
+    }
+    let exact = match expr {
+        Expression::Add(_, unit) | Expression::Sub(_, unit) | Expression::Jitter(_, unit) => {
+            unit > Unit::Day
+        }
+        Expression::AssertFloor(_) => true,
+        _ => false,
+    };
+    match time.ambiguous_local_time() {
+        Some(local) if !exact => Err(Error::AmbiguousLocalTime(local)),
+        _ => Ok(()),
+    }
+}
+
+/// Applies the terms in `text` to `value`, e.g. `+1d/d` is the start of the day
+/// after `value`. Floors may come first, as `value` takes the place of a
+/// leading `now`. A `now` in `text` also stands for `value`, like
+/// [`parse_str_with_now`].
+pub fn apply_str<T: RelativeTime>(value: T, text: &str) -> Result<T> {
+    let exprs = spanned(Parser::new(text)).collect::<Result<Vec<_>>>()?;
+    if exprs
+        .iter()
+        .any(|(_, expr)| matches!(expr, Expression::Now(_) | Expression::Epoch(_)))
+    {
+        return parse_str_with_now(text, value);
+    }
+    apply(value, exprs.into_iter().map(Ok), ParseOptions::default())
+}
+
+/// Applies the offsets in `text` to `base`, for settings that hold a duration
+/// like `+30m` or `1h ago`, where the leading `+` is optional. A `now` or
+/// `epoch` results in [`Error::NowNotAllowed`], and floors and weekdays in
+/// [`Error::UnsupportedExpression`].
+pub fn parse_pure_offset_str<T: RelativeTime>(text: &str, base: T) -> Result<T> {
+    let exprs = spanned(Parser::new(text)).collect::<Result<Vec<_>>>()?;
+    if exprs.is_empty() {
+        return Err(Error::NoExpressions);
+    }
+    for &(_, expr) in &exprs {
+        match expr {
+            Expression::Add(..) | Expression::Sub(..) => {}
+            Expression::Now(position) | Expression::Epoch(position) => {
+                return Err(Error::NowNotAllowed(position));
+            }
+            expr => return Err(Error::UnsupportedExpression(expr)),
+        }
+    }
+    apply(base, exprs.into_iter().map(Ok), ParseOptions::default())
+}
+
+/// Replaces every `{expression}` in `template` by the expression resolved
+/// relative to `now` and formatted with `format`, e.g. `from {now-1d} to
+/// {now}`. `{{` and `}}` stand for literal braces. Errors report positions in
+/// the whole `template`.
+pub fn resolve_in_text_with<T: RelativeTime>(
+    template: &str,
+    now: &T,
+    format: impl Fn(&T) -> String,
+) -> Result<String> {
+    let mut resolved = String::with_capacity(template.len());
+    let mut index = 0;
+    while let Some(offset) = template[index..].find(['{', '}']) {
+        let brace = index + offset;
+        resolved.push_str(&template[index..brace]);
+        let escaped = &template[brace..=brace];
+        if template[brace + 1..].starts_with(escaped) {
+            resolved.push_str(escaped);
+            index = brace + 2;
+            continue;
+        }
+        if escaped == "}" {
+            return Err(Error::UnmatchedBrace(brace));
+        }
+        let end = template[brace..]
+            .find('}')
+            .map(|offset| brace + offset)
+            .ok_or(Error::UnmatchedBrace(brace))?;
+        let lexer = Lexer::new(&template[..end]).starting_at(brace + 1);
+        let time = evaluate(
+            spanned(Parser::from_lexer(lexer)),
+            now.clone(),
+            ParseOptions::default(),
+        )?;
+        resolved.push_str(&format(&time));
+        index = end + 1;
+    }
+    resolved.push_str(&template[index..]);
+    Ok(resolved)
+}
+
+/// Resolves `text` relative to `now`, together with the difference between the
+/// components of the result and those of `now`.
+pub fn parse_str_delta<T>(text: &str, now: T) -> Result<(T, TimeComponents)>
+where
+    T: RelativeTime,
+    for<'t> TimeComponents: From<&'t T>,
+{
+    let before = TimeComponents::from(&now);
+    let time = parse_str_with_now(text, now)?;
+    let delta = TimeComponents::from(&time) - before;
+    Ok((time, delta))
+}
+
+/// Resolves `a` and `b` relative to the same `now`, returning the difference
+/// between the components of `b` and those of `a`. Each text still needs its
+/// own single `now`.
+pub fn duration_between<T>(a: &str, b: &str, now: T) -> Result<TimeComponents>
+where
+    T: RelativeTime,
+    for<'t> TimeComponents: From<&'t T>,
+{
+    let start = parse_str_with_now(a, now.clone())?;
+    let end = parse_str_with_now(b, now)?;
+    Ok(TimeComponents::from(&end) - TimeComponents::from(&start))
+}
+
+/// Resolves many expressions against the same `now`, e.g. all panels of a
+/// dashboard, which is taken once when creating the resolver.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Resolver<T> {
+    pub now: T,
+}
+
+impl<T: RelativeTime> Resolver<T> {
+    pub fn new(now: T) -> Self {
+        Self { now }
+    }
+
+    /// Resolves `text` relative to the stored `now`, like [`parse_str_with_now`].
+    pub fn resolve(&self, text: &str) -> Result<T> {
+        parse_str_with_now(text, self.now.clone())
+    }
+}
+
+/// Resolves expressions like [`parse_str_with_now`], keeping the parsed terms
+/// of the most recently used texts, so texts that come back often are parsed
+/// once. Texts that fail to parse are not kept.
+#[derive(Debug, Clone, Default)]
+pub struct CachedResolver {
+    capacity: usize,
+    // the least recently used text comes first
+    entries: VecDeque<(String, Vec<(usize, Expression)>)>,
+    parses: usize,
+}
+
+impl CachedResolver {
+    /// Keeps up to `capacity` texts.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+            parses: 0,
+        }
+    }
+
+    pub fn resolve<T: RelativeTime>(&mut self, text: &str, now: T) -> Result<T> {
+        let cached = self
+            .entries
+            .iter()
+            .position(|(cached, _)| cached == text)
+            .and_then(|index| self.entries.remove(index));
+        let (text, exprs) = if let Some(entry) = cached {
+            entry
+        } else {
+            self.parses += 1;
+            let exprs = spanned(Parser::new(text)).collect::<Result<Vec<_>>>()?;
+            (text.to_string(), exprs)
+        };
+        let time = evaluate(exprs.iter().copied().map(Ok), now, ParseOptions::default());
+        if self.capacity > 0 {
+            if self.entries.len() == self.capacity {
+                self.entries.pop_front();
+            }
+            self.entries.push_back((text, exprs));
+        }
+        time
+    }
+
+    /// How many times a text had to be parsed, as it was not kept.
+    pub fn parses(&self) -> usize {
+        self.parses
+    }
+}
+
+/// An expression that is parsed once and resolved against any number of
+/// times, like [`parse_str_with_now`]. Unlike [`CachedResolver`] it is
+/// immutable, so it can be shared between threads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    exprs: Vec<(usize, Expression)>,
+}
+
+impl Program {
+    /// Parses `text` and checks that it has a single `now` or `epoch`, with no
+    /// floor before it. Errors of the terms themselves, like an overflow, only
+    /// show up when resolving.
+    pub fn parse(text: &str) -> Result<Self> {
+        let exprs = spanned(Parser::new(text)).collect::<Result<Vec<_>>>()?;
+        let start = exprs.iter().position(|(_, expr)| {
+            matches!(expr, Expression::Now(_) | Expression::Epoch(_)) || is_floor(expr)
+        });
+        let Some(start) = start else {
+            return Err(Error::MissingNow);
+        };
+        if !matches!(exprs[start].1, Expression::Now(_) | Expression::Epoch(_)) {
+            return Err(Error::FloorBeforeNow);
+        }
+        for &(_, expr) in &exprs[start + 1..] {
+            if let Expression::Now(position) | Expression::Epoch(position) = expr {
+                return Err(Error::MultipleNow(position));
+            }
+        }
+        Ok(Self { exprs })
+    }
+
+    /// Resolves the expression relative to `now`, which gives the same result
+    /// as [`parse_str_with_now`] on the parsed text.
+    pub fn resolve<T: RelativeTime>(&self, now: T) -> Result<T> {
+        evaluate(
+            self.exprs.iter().copied().map(Ok),
+            now,
+            ParseOptions::default(),
+        )
+    }
+}
+
+fn check_magnitude(index: usize, expr: Expression, max: Option<(u32, Unit)>) -> Result<()> {
+    let (
+        Some((max, max_unit)),
+        Expression::Add(value, unit)
+        | Expression::Sub(value, unit)
+        | Expression::Jitter(value, unit),
+    ) = (max, expr)
+    else {
+        return Ok(());
+    };
+    if magnitude(value, unit) > magnitude(max, max_unit) {
+        return Err(Error::OffsetTooLarge(index, unit, value.into()));
+    }
+    Ok(())
+}
+
+fn is_end_of_month<T: RelativeTime>(time: &T, expr: Expression) -> Result<bool> {
+    let next = time.clone().add_days(1)?;
+    Ok(next
+        .day_of_month()
+        .ok_or(Error::UnsupportedExpression(expr))?
+        == 1)
+}
+
+// Moves forward to the last day of the month, keeping the time of day.
+fn to_end_of_month<T: RelativeTime>(time: T, expr: Expression) -> Result<T> {
+    let day = |time: &T| {
+        time.day_of_month()
+            .ok_or(Error::UnsupportedExpression(expr))
+    };
+    let last = time.clone().floor_months()?.add_months(1)?.sub_days(1)?;
+    let days = day(&last)?.saturating_sub(day(&time)?);
+    time.add_days(days)
+}
+
+// Adds the days that were clamped off by adding years or months back on.
+fn spill<T: RelativeTime>(time: T, expr: Expression) -> Result<T> {
+    let day = time
+        .day_of_month()
+        .ok_or(Error::UnsupportedExpression(expr))?;
+    let clamped = match expr {
+        Expression::Add(value, Unit::Year) => time.add_years(value),
+        Expression::Sub(value, Unit::Year) => time.sub_years(value),
+        Expression::Add(value, _) => time.add_months(value),
+        Expression::Sub(value, _) => time.sub_months(value),
+        _ => Ok(time),
+    }?;
+    let clamped_day = clamped
+        .day_of_month()
+        .ok_or(Error::UnsupportedExpression(expr))?;
+    clamped.add_days(day.saturating_sub(clamped_day))
+}
+
+// The approximate length of a term in seconds, only used to compare terms.
+fn magnitude(value: u32, unit: Unit) -> u128 {
+    const MICROS_PER_SECOND: u128 = 1_000_000;
+    const MICROS_PER_DAY: u128 = 24 * 60 * 60 * MICROS_PER_SECOND;
+    let micros = match unit {
+        Unit::Year => 366 * MICROS_PER_DAY,
+        Unit::Month => 366 * MICROS_PER_DAY / 12,
+        Unit::Week => 7 * MICROS_PER_DAY,
+        Unit::Day => MICROS_PER_DAY,
+        Unit::Hour => 60 * 60 * MICROS_PER_SECOND,
+        Unit::Minute => 60 * MICROS_PER_SECOND,
+        Unit::Second => MICROS_PER_SECOND,
+        Unit::Millisecond => 1_000,
+        Unit::Microsecond => 1,
+    };
+    u128::from(value) * micros
+}
+
+fn floor_business_hours<T: RelativeTime>(time: T, options: ParseOptions) -> Result<T> {
+    let hours = options
+        .business_hours
+        .ok_or(Error::UnsupportedExpression(Expression::FloorBusinessHours))?;
+    time.floor_business_hours(hours)
+}
+
+// Like `floor` for the minutes of `/q` and `/H`.
+fn floor_minutes_by<T: RelativeTime>(time: &T, minutes: u32, options: ParseOptions) -> Result<T> {
+    if options.reject_subday_floor {
+        return Err(Error::UnsupportedFloor(Unit::Minute));
+    }
+    let floored = time.clone().floor_minutes_by(minutes)?;
+    if options.floor_exclusive && floored == *time {
+        floored.sub_minutes(minutes)
+    } else {
+        Ok(floored)
+    }
+}
+
+fn floor<T: RelativeTime>(time: &T, unit: Unit, options: ParseOptions) -> Result<T> {
+    if options.reject_subday_floor && unit > Unit::Day {
+        return Err(Error::UnsupportedFloor(unit));
+    }
+    let (floored, moved) = match unit {
+        Unit::Week if options.week_mode != WeekMode::IsoMonday => {
+            let floored = floor_week(time.clone(), options.week_mode)?;
+            let moved = floored != *time;
+            (floored, moved)
+        }
+        _ => time.clone().floor_checked(unit)?,
+    };
+    if !options.floor_exclusive || moved {
+        return Ok(floored);
+    }
+    match unit {
+        Unit::Year => floored.sub_years(1),
+        Unit::Month => floored.sub_months(1),
+        // the week before the first of January can be shorter
+        Unit::Week if options.week_mode == WeekMode::YearRelative => {
+            floor_week(floored.sub_days(1)?, options.week_mode)
+        }
+        Unit::Week => floored.sub_weeks(1),
+        Unit::Day => floored.sub_days(1),
+        Unit::Hour => floored.sub_hours(1),
+        Unit::Minute => floored.sub_minutes(1),
+        Unit::Second => floored.sub_seconds(1),
+        Unit::Millisecond => floored.sub_millis(1),
+        Unit::Microsecond => floored.sub_micros(1),
+    }
+}
+
+fn floor_week<T: RelativeTime>(time: T, mode: WeekMode) -> Result<T> {
+    if mode == WeekMode::IsoMonday {
+        return time.floor_weeks();
+    }
+    if time.day_of_week().is_none() {
+        return Err(Error::UnsupportedUnit(Unit::Week));
+    }
+    let weekday = match mode {
+        // `1970-01-01` was a thursday
+        WeekMode::EpochAligned => Weekday::Thursday,
+        WeekMode::IsoMonday => Weekday::Monday,
+        WeekMode::CalendarSunday => Weekday::Sunday,
+        WeekMode::YearRelative => time
+            .clone()
+            .floor_years()?
+            .day_of_week()
+            .ok_or(Error::UnsupportedUnit(Unit::Week))?,
+    };
+    time.floor_weekday(weekday)
+}
+
+// Floors to the week, but not past the first of the month `time` is in.
+fn floor_week_in_month<T: RelativeTime>(time: &T, options: ParseOptions) -> Result<T> {
+    let day = |time: &T| {
+        time.day_of_month()
+            .ok_or(Error::UnsupportedExpression(Expression::Floor(Unit::Week)))
+    };
+    let floored = floor(time, Unit::Week, options)?;
+    // floors move back less than a month, so the day only grows when they
+    // cross into the previous one
+    if day(&floored)? > day(time)? {
+        time.clone().floor_months()
+    } else {
+        Ok(floored)
+    }
+}
+
+fn to_weekday<T: RelativeTime>(
+    time: T,
+    current: Weekday,
+    qualifier: Qualifier,
+    weekday: Weekday,
+) -> Result<T> {
+    let ahead = (weekday.num_days_from_monday() + DAYS_PER_WEEK - current.num_days_from_monday())
+        % DAYS_PER_WEEK;
+    match qualifier {
+        Qualifier::Next if ahead == 0 => time.add_days(DAYS_PER_WEEK),
+        Qualifier::This | Qualifier::Next => time.add_days(ahead),
+        Qualifier::Last => time.sub_days(DAYS_PER_WEEK - ahead),
+    }
+}
+
+/// The earliest of `texts`, each resolved relative to the same `now`, e.g. the
+/// deadline that comes first. The first text that fails to resolve gives its
+/// error, and no texts at all is an [`Error::NoExpressions`].
+pub fn earliest<T: RelativeTime + Ord>(texts: &[&str], now: T) -> Result<T> {
+    resolve_all(texts, now)?
+        .into_iter()
+        .min()
+        .ok_or(Error::NoExpressions)
+}
+
+/// The latest of `texts`, each resolved relative to the same `now`, like
+/// [`earliest`].
+pub fn latest<T: RelativeTime + Ord>(texts: &[&str], now: T) -> Result<T> {
+    resolve_all(texts, now)?
+        .into_iter()
+        .max()
+        .ok_or(Error::NoExpressions)
+}
+
+/// Resolves `text` relative to `now` and clamps the result into `min..=max`,
+/// e.g. to never go before the epoch. Clamping is silent, see
+/// [`parse_str_clamped_checked`] to tell whether it happened.
+pub fn parse_str_clamped<T>(text: &str, now: T, min: T, max: T) -> Result<T>
+where
+    T: RelativeTime + Ord,
+{
+    parse_str_clamped_checked(text, now, min, max).map(|(time, _)| time)
+}
+
+/// Like [`parse_str_clamped`], also reporting whether the result was out of
+/// range and replaced by `min` or `max`. With `min` after `max`, results below
+/// `min` still clamp to `min`.
+pub fn parse_str_clamped_checked<T>(text: &str, now: T, min: T, max: T) -> Result<(T, bool)>
+where
+    T: RelativeTime + Ord,
+{
+    let time = parse_str_with_now(text, now)?;
+    Ok(if time < min {
+        (min, true)
+    } else if time > max {
+        (max, true)
+    } else {
+        (time, false)
+    })
+}
+
+fn resolve_all<T: RelativeTime>(texts: &[&str], now: T) -> Result<Vec<T>> {
+    texts
+        .iter()
+        .zip(std::iter::repeat_n(now, texts.len()))
+        .map(|(text, now)| parse_str_with_now(text, now))
+        .collect()
+}
+
+pub fn parse_seconds(text: &str) -> Result<i64> {
+    let mut now = false;
+    let mut seconds = 0i64;
+
+    for expr in Parser::new(text) {
+        let expr = expr?;
+        let (value, unit) = match expr {
+            Expression::Now(index) if now => return Err(Error::MultipleNow(index)),
+            Expression::Now(_) => {
+                now = true;
+                continue;
+            }
+            // the same error as resolving the text would give
+            expr if !now && is_floor(&expr) => return Err(Error::FloorBeforeNow),
+            Expression::Add(value, unit) => (i64::from(value), unit),
+            Expression::Sub(value, unit) => (-i64::from(value), unit),
+            Expression::Floor(unit) | Expression::AssertFloor(unit) => {
+                return Err(Error::UnsupportedUnit(unit));
+            }
+            Expression::Epoch(_)
+            | Expression::Weekday(..)
+            | Expression::FloorWeekday(_)
+            | Expression::FloorIsoYear
+            | Expression::FloorBusinessHours
+            | Expression::FloorMinutes(_)
+            | Expression::NthWeekday(..)
+            | Expression::Jitter(..) => {
+                return Err(Error::UnsupportedExpression(expr));
+            }
+        };
+        let factor = match unit {
+            Unit::Year | Unit::Month | Unit::Millisecond | Unit::Microsecond => {
+                return Err(Error::UnsupportedUnit(unit));
+            }
+            Unit::Week => DAYS_PER_WEEK * HOURS_PER_DAY * MINUTES_PER_HOUR * SECONDS_PER_MINUTE,
+            Unit::Day => HOURS_PER_DAY * MINUTES_PER_HOUR * SECONDS_PER_MINUTE,
+            Unit::Hour => MINUTES_PER_HOUR * SECONDS_PER_MINUTE,
+            Unit::Minute => SECONDS_PER_MINUTE,
+            Unit::Second => 1,
+        };
+        seconds = value
+            .checked_mul(factor.into())
+            .and_then(|value| seconds.checked_add(value))
+            .ok_or(Error::InvalidDelta)?;
+    }
+
+    if !now {
+        return Err(Error::MissingNow);
+    }
+    Ok(seconds)
+}
+
+// A part of the summary of [`describe`].
+enum Step {
+    /// Offsets in a row that move the same way, e.g. `1 day` and `2 hours`.
+    Offsets(bool, Vec<String>),
+    Other(String),
+}
+
+/// Summarizes `text` in English, e.g. `now+1d+2h` is `1 day and 2 hours from
+/// the base time`, for tooltips and the like. Terms are described in the order
+/// they are applied, offsets of zero are left out.
+pub fn describe(text: &str) -> Result<String> {
+    let mut base = None;
+    let mut steps = Vec::new();
+
+    for expr in spanned(Parser::new(text)) {
+        let (_, expr) = expr?;
+        let (later, value, unit) = match expr {
+            Expression::Now(position) | Expression::Epoch(position) if base.is_some() => {
+                return Err(Error::MultipleNow(position));
+            }
+            Expression::Now(_) => {
+                base = Some("the base time");
+                continue;
+            }
+            Expression::Epoch(_) => {
+                base = Some("the unix epoch");
+                continue;
+            }
+            expr if base.is_none() && is_floor(&expr) => return Err(Error::FloorBeforeNow),
+            Expression::Add(value, unit) => (true, value, unit),
+            Expression::Sub(value, unit) => (false, value, unit),
+            Expression::Floor(unit) => {
+                let step = format!("snapped to the start of the {unit}");
+                steps.push(Step::Other(step));
+                continue;
+            }
+            Expression::Weekday(qualifier, weekday) => {
+                steps.push(Step::Other(format!("then {qualifier} {weekday}")));
+                continue;
+            }
+            Expression::FloorWeekday(weekday) => {
+                steps.push(Step::Other(format!("snapped back to {weekday}")));
+                continue;
+            }
+            Expression::FloorIsoYear => {
+                let step = "snapped to the start of the ISO year".to_string();
+                steps.push(Step::Other(step));
+                continue;
+            }
+            Expression::FloorBusinessHours => {
+                steps.push(Step::Other("snapped back to business hours".to_string()));
+                continue;
+            }
+            Expression::AssertFloor(unit) => {
+                let step = format!("checked to be at the start of the {unit}");
+                steps.push(Step::Other(step));
+                continue;
+            }
+            Expression::FloorMinutes(minutes) => {
+                let step = format!("snapped to a multiple of {minutes} minutes");
+                steps.push(Step::Other(step));
+                continue;
+            }
+            Expression::NthWeekday(n, weekday) => {
+                let step = format!("snapped to {weekday} number {n} of the month");
+                steps.push(Step::Other(step));
+                continue;
+            }
+            Expression::Jitter(value, unit) => {
+                let plural = if value == 1 { "" } else { "s" };
+                let step = format!("give or take up to {value} {unit}{plural}");
+                steps.push(Step::Other(step));
+                continue;
+            }
+        };
+        if value == 0 {
+            continue;
+        }
+        let plural = if value == 1 { "" } else { "s" };
+        let term = format!("{value} {unit}{plural}");
+        match steps.last_mut() {
+            Some(Step::Offsets(direction, terms)) if *direction == later => terms.push(term),
+            _ => steps.push(Step::Offsets(later, vec![term])),
+        }
+    }
+
+    let base = base.ok_or(Error::MissingNow)?;
+    let mut steps = steps.into_iter().peekable();
+    let mut description = match steps.next_if(|step| matches!(step, Step::Offsets(..))) {
+        Some(Step::Offsets(true, terms)) => format!("{} from {base}", join_terms(&terms)),
+        Some(Step::Offsets(false, terms)) => format!("{} before {base}", join_terms(&terms)),
+        _ => base.to_string(),
+    };
+    for step in steps {
+        match step {
+            Step::Offsets(true, terms) => {
+                description += &format!(", then {} later", join_terms(&terms));
+            }
+            Step::Offsets(false, terms) => {
+                description += &format!(", then {} earlier", join_terms(&terms));
+            }
+            Step::Other(step) => description += &format!(", {step}"),
+        }
+    }
+    Ok(description)
+}
+
+// Joins `terms` like `1 year, 2 months and 3 days`.
+fn join_terms(terms: &[String]) -> String {
+    match terms.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {last}", rest.join(", ")),
+        None => String::new(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::{Local, NaiveDateTime, TimeZone};
+    use crate::error::TokenType;
+    use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Timelike};
 
     macro_rules! test_input_string {
         ($input:expr, $output:expr) => {
@@ -147,11 +1370,47 @@ mod tests {
         };
     }
 
+    // The time most tests resolve relative to, 2023-08-21T05:40:00 in `tz`.
+    fn base<Tz: TimeZone>(tz: &Tz) -> DateTime<Tz> {
+        tz.with_ymd_and_hms(2023, 8, 21, 5, 40, 0)
+            .single()
+            .expect("input date time ambiguous")
+    }
+
     #[test]
     fn literal_now() {
         test_input_string!("now", "2023-08-21T05:40:00");
     }
 
+    #[test]
+    fn month_alias() {
+        test_input_string!("now+1mo", "2023-09-21T05:40:00");
+        test_input_string!("now+1m", "2023-08-21T05:41:00");
+        test_input_string!("now+1mo+1m", "2023-09-21T05:41:00");
+        test_input_string!("now-2mo/mo", "2023-06-01T00:00:00");
+    }
+
+    #[test]
+    fn epoch() {
+        let now = Local::now();
+        let epoch = DateTime::UNIX_EPOCH.with_timezone(&Local);
+        assert_eq!(parse_str_with_now("epoch", now), Ok(epoch));
+        assert_eq!(parse_str_with_now("epoch+1d", now), epoch.add_days(1));
+        assert_eq!(parse_str_with_now("+1d+epoch", now), epoch.add_days(1));
+        assert_eq!(
+            parse_str_with_now("now+epoch", now),
+            Err(Error::MultipleNow(4))
+        );
+        assert_eq!(
+            parse_str_with_now("epoch+1d+epoch", now),
+            Err(Error::MultipleNow(9))
+        );
+        assert_eq!(
+            parse_seconds("epoch+1d"),
+            Err(Error::UnsupportedExpression(Expression::Epoch(0)))
+        );
+    }
+
     #[test]
     fn unary_now() {
         test_input_string!("+now", "2023-08-21T05:40:00");
@@ -190,6 +1449,85 @@ mod tests {
         test_input_string!("now + 1d - 2h + 30m", "2023-08-22T04:10:00");
     }
 
+    #[test]
+    fn repeat() {
+        test_input_string!("now+15m*4", "2023-08-21T06:40:00");
+        test_input_string!("now - 1d * 2 + 1h*0", "2023-08-19T05:40:00");
+        let now = Local::now();
+        assert_eq!(
+            parse_str_with_now("now+15m*4", now),
+            parse_str_with_now("now+1h", now)
+        );
+        assert_eq!(
+            parse_str_with_now("now+1y*4294967295", now),
+            Err(Error::OffsetTooLarge(6, Unit::Year, 4_294_967_295))
+        );
+        // the most years that fit as months
+        assert_eq!(
+            parse_str_with_now("now+1y*357913941", now),
+            Err(Error::OverflowAt(
+                3,
+                Expression::Add(357_913_941, Unit::Year)
+            ))
+        );
+        assert_eq!(
+            parse_str_with_now("now+1y*357913942", now),
+            Err(Error::OffsetTooLarge(6, Unit::Year, 357_913_942))
+        );
+        assert_eq!(
+            parse_str_with_now("now+1M*4294967295", now),
+            Err(Error::OverflowAt(
+                3,
+                Expression::Add(4_294_967_295, Unit::Month)
+            ))
+        );
+        assert_eq!(
+            parse_str_with_now("now+2y*4294967295", now),
+            Err(Error::OffsetTooLarge(6, Unit::Year, 8_589_934_590))
+        );
+    }
+
+    #[test]
+    fn jitter() {
+        let now = DateTime::<chrono::Utc>::UNIX_EPOCH;
+        let jittered = |seed| {
+            let options = ParseOptions {
+                jitter_seed: seed,
+                ..Default::default()
+            };
+            parse_str_with_options("now+1h~5m", now, options).expect("input should resolve")
+        };
+        let earliest = now + chrono::TimeDelta::minutes(55);
+        let latest = now + chrono::TimeDelta::minutes(65);
+
+        assert_eq!(jittered(Some(42)), jittered(Some(42)));
+        let times = (0..64)
+            .map(|seed| jittered(Some(seed)))
+            .collect::<std::collections::BTreeSet<_>>();
+        assert!(times.len() > 1, "every seed gave the same time");
+        for time in times.into_iter().chain([jittered(None)]) {
+            assert!((earliest..=latest).contains(&time), "{time} out of range");
+            assert_eq!(time.second(), 0, "{time} is not a whole minute");
+        }
+
+        assert_eq!(parse_str_with_now("now~0d", now), Ok(now));
+        let options = ParseOptions {
+            max_magnitude: Some((10, Unit::Minute)),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_str_with_options("now~11m", now, options),
+            Err(Error::OffsetTooLarge(3, Unit::Minute, 11))
+        );
+        assert_eq!(
+            parse_seconds("now~5m"),
+            Err(Error::UnsupportedExpression(Expression::Jitter(
+                5,
+                Unit::Minute
+            )))
+        );
+    }
+
     #[test]
     fn now_with_whitespace_variants() {
         test_input_string!("now+1d", "2023-08-22T05:40:00");
@@ -198,12 +1536,147 @@ mod tests {
     }
 
     #[test]
-    fn test_leading_zeroes() {
-        test_input_string!("now + 00015s", "2023-08-21T05:40:15");
+    fn bytes() {
+        let now = std::time::Duration::from_secs(1 << 20);
+        assert_eq!(
+            parse_bytes_with_now(b"now - 1d + 2h", now),
+            parse_str_with_now("now - 1d + 2h", now)
+        );
+        assert_eq!(
+            parse_bytes_with_now(b"now+1d\x80", now),
+            Err(Error::UnexpectedByte(6, 0x80))
+        );
     }
 
     #[test]
-    fn now_with_with_overflow_addition() {
+    fn resolver() {
+        let resolver = Resolver::new(std::time::Duration::from_secs(10 * 24 * 60 * 60));
+        assert_eq!(
+            resolver.resolve("now-1d"),
+            Ok(std::time::Duration::from_secs(9 * 24 * 60 * 60))
+        );
+        assert_eq!(
+            resolver.resolve("now+9h"),
+            Ok(std::time::Duration::from_secs((10 * 24 + 9) * 60 * 60))
+        );
+        assert_eq!(resolver.resolve("now+now"), Err(Error::MultipleNow(4)));
+    }
+
+    #[test]
+    fn truncated_now() {
+        let now: DateTime<Local> =
+            parse_str_truncated("now", Unit::Second).expect("now should resolve");
+        assert_eq!(now.nanosecond(), 0);
+        let tomorrow: DateTime<Local> =
+            parse_str_truncated("now+1d-1s", Unit::Second).expect("now should resolve");
+        assert_eq!(tomorrow.nanosecond(), 0);
+        let now: DateTime<Local> =
+            parse_str_truncated("now", Unit::Microsecond).expect("now should resolve");
+        assert_eq!(now.nanosecond() % 1_000, 0);
+    }
+
+    #[test]
+    fn clamped() {
+        let now = base(&Local);
+        let min = DateTime::UNIX_EPOCH.with_timezone(&Local);
+        let max = now.add_years(1).expect("max should fit");
+
+        assert_eq!(parse_str_clamped("now-100y", now, min, max), Ok(min));
+        assert_eq!(
+            parse_str_clamped_checked("now-100y", now, min, max),
+            Ok((min, true))
+        );
+        assert_eq!(
+            parse_str_clamped_checked("now-10y", now, min, max),
+            now.sub_years(10).map(|time| (time, false))
+        );
+        assert_eq!(
+            parse_str_clamped_checked("now+1y", now, min, max),
+            Ok((max, false))
+        );
+        assert_eq!(
+            parse_str_clamped_checked("now+1y+1s", now, min, max),
+            Ok((max, true))
+        );
+        assert_eq!(
+            parse_str_clamped("now+1x", now, min, max),
+            Err(Error::UnexpectedCharacter(5, 'x'))
+        );
+    }
+
+    #[test]
+    fn program() {
+        let hours = |hours: u64| std::time::Duration::from_secs(hours * 60 * 60);
+        let program = Program::parse("now-1d+2h").expect("input should parse");
+
+        assert_eq!(program.resolve(hours(48)), Ok(hours(26)));
+        assert_eq!(program.resolve(hours(24)), Ok(hours(2)));
+        let shared = &program;
+        std::thread::scope(|scope| {
+            let handle = scope.spawn(|| shared.resolve(hours(30)));
+            assert_eq!(handle.join().ok(), Some(Ok(hours(8))));
+        });
+        assert_eq!(program.resolve(hours(1)), Err(Error::NegativeDuration));
+
+        assert_eq!(Program::parse("+1d"), Err(Error::MissingNow));
+        assert_eq!(Program::parse("1d/d+now"), Err(Error::FloorBeforeNow));
+        assert_eq!(Program::parse("now+1d+now"), Err(Error::MultipleNow(7)));
+        assert_eq!(Program::parse("now+epoch"), Err(Error::MultipleNow(4)));
+        assert_eq!(
+            Program::parse("now+1x"),
+            Err(Error::UnexpectedCharacter(5, 'x'))
+        );
+    }
+
+    #[test]
+    fn cached_resolver() {
+        let hours = |hours: u64| std::time::Duration::from_secs(hours * 60 * 60);
+        let mut resolver = CachedResolver::new(2);
+
+        assert_eq!(resolver.resolve("now-1h", hours(48)), Ok(hours(47)));
+        assert_eq!(resolver.resolve("now-1h", hours(24)), Ok(hours(23)));
+        assert_eq!(resolver.resolve("now-24h", hours(48)), Ok(hours(24)));
+        assert_eq!(resolver.parses(), 2);
+
+        // uses `now-1h`, so `now-24h` is dropped for `now+1h`
+        assert_eq!(resolver.resolve("now-1h", hours(2)), Ok(hours(1)));
+        assert_eq!(resolver.resolve("now+1h", hours(2)), Ok(hours(3)));
+        assert_eq!(resolver.resolve("now-1h", hours(3)), Ok(hours(2)));
+        assert_eq!(resolver.parses(), 3);
+        assert_eq!(resolver.resolve("now-24h", hours(24)), Ok(hours(0)));
+        assert_eq!(resolver.parses(), 4);
+
+        // errors while parsing are not kept, errors while resolving are
+        for _ in 0..3 {
+            assert_eq!(
+                resolver.resolve("now+1x", hours(1)),
+                Err(Error::UnexpectedCharacter(5, 'x'))
+            );
+        }
+        assert_eq!(resolver.parses(), 7);
+        assert_eq!(
+            resolver.resolve("now-2h", hours(1)),
+            Err(Error::NegativeDuration)
+        );
+        assert_eq!(
+            resolver.resolve("now-2h", hours(1)),
+            Err(Error::NegativeDuration)
+        );
+        assert_eq!(resolver.parses(), 8);
+
+        let mut uncached = CachedResolver::new(0);
+        assert_eq!(uncached.resolve("now-1h", hours(2)), Ok(hours(1)));
+        assert_eq!(uncached.resolve("now-1h", hours(2)), Ok(hours(1)));
+        assert_eq!(uncached.parses(), 2);
+    }
+
+    #[test]
+    fn test_leading_zeroes() {
+        test_input_string!("now + 00015s", "2023-08-21T05:40:15");
+    }
+
+    #[test]
+    fn now_with_with_overflow_addition() {
         test_input_string!("now + 90s", "2023-08-21T05:41:30");
         test_input_string!("now + 61m", "2023-08-21T06:41:00");
     }
@@ -226,4 +1699,1204 @@ mod tests {
             "2024-08-09T09:10:15"
         );
     }
+
+    #[test]
+    fn floor_interleaved() {
+        test_input_string!("now+1M/M+14d", "2023-09-15T00:00:00");
+        test_input_string!("now/M+1M+14d", "2023-09-15T00:00:00");
+        test_input_string!("now+14d/M", "2023-09-01T00:00:00");
+        test_input_string!("now/y+1y/M-1d", "2023-12-31T00:00:00");
+        test_input_string!("now/d+36h/d+1h/h", "2023-08-22T01:00:00");
+    }
+
+    #[test]
+    fn floor_is_barrier() {
+        // folding `-6h` and `+6h` into a no-op would give `2023-08-21T00:00:00`
+        test_input_string!("now-6h/d+6h", "2023-08-20T06:00:00");
+        test_input_string!("now+6h-6h/d", "2023-08-21T00:00:00");
+    }
+
+    #[test]
+    fn delta() {
+        let now = base(&Local);
+
+        let (time, delta) = parse_str_delta("now+1d+2h", now).expect("input should parse");
+        assert_eq!(time, now + chrono::TimeDelta::hours(26));
+        assert_eq!(
+            delta,
+            TimeComponents {
+                days: 1,
+                hours: 2,
+                ..Default::default()
+            }
+        );
+
+        let (_, delta) = parse_str_delta("now/M", now).expect("input should parse");
+        assert_eq!(
+            delta,
+            TimeComponents {
+                days: -20,
+                hours: -5,
+                minutes: -40,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn add_components() {
+        let now = base(&Local);
+        let delta = TimeComponents {
+            years: 1,
+            months: -2,
+            days: 3,
+            hours: 4,
+            minutes: -5,
+            seconds: 6,
+            nanos: 7,
+        };
+
+        assert_eq!(
+            now.add_components(&delta),
+            parse_str_with_now("now+1y-2M+3d+4h-5m+6s", now).and_then(|time| time.add_nanos(7))
+        );
+        let (time, delta) = parse_str_delta("now-1d+2h", now).expect("input should parse");
+        assert_eq!(now.add_components(&delta), Ok(time));
+        assert_eq!(now.add_components(&TimeComponents::default()), Ok(now));
+        assert_eq!(
+            now.add_components(&TimeComponents {
+                days: i64::from(u32::MAX) + 1,
+                ..Default::default()
+            }),
+            Err(Error::InvalidDelta)
+        );
+        let duration = TimeComponents {
+            hours: 2,
+            minutes: 30,
+            ..Default::default()
+        };
+        assert_eq!(
+            std::time::Duration::ZERO.add_components(&duration),
+            Ok(std::time::Duration::from_secs(9000))
+        );
+        assert_eq!(
+            std::time::Duration::ZERO.add_components(&TimeComponents {
+                years: 1,
+                ..duration
+            }),
+            Err(Error::UnsupportedUnit(Unit::Year))
+        );
+    }
+
+    #[test]
+    fn resolve_checked_spans() {
+        let now = DateTime::<chrono::Utc>::MIN_UTC;
+        let error = |text| resolve_checked(text, now).map(|res| res.to_rfc3339());
+        assert_eq!(
+            error("now+1x"),
+            Err(ResolveError {
+                kind: ErrorKind::Lex,
+                start: 5,
+                end: 6,
+                error: Error::UnexpectedCharacter(5, 'x'),
+            })
+        );
+        assert_eq!(
+            error("now+next"),
+            Err(ResolveError {
+                kind: ErrorKind::Parse,
+                start: 4,
+                end: 8,
+                error: Error::InvalidFormat(4, TokenType::Value, TokenType::Next),
+            })
+        );
+        assert_eq!(
+            error("now+1d -"),
+            Err(ResolveError {
+                kind: ErrorKind::Parse,
+                start: 8,
+                end: 8,
+                error: Error::UnexpectedEndOfInput(8, TokenType::Value),
+            })
+        );
+        assert_eq!(
+            error("now+1d - 1y /d"),
+            Err(ResolveError {
+                kind: ErrorKind::Resolve,
+                start: 7,
+                end: 11,
+                error: Error::OverflowAt(7, Expression::Sub(1, Unit::Year)),
+            })
+        );
+        assert_eq!(
+            error("now+1d - 1y /d").map_err(|err| err.span()),
+            Err(7..11)
+        );
+        for text in ["now tu", "now nex", "no"] {
+            assert_eq!(
+                error(text).map_err(|err| (err.kind, err.span())),
+                Err((ErrorKind::Lex, text.len()..text.len())),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            error("now+1M/5fri"),
+            Err(ResolveError {
+                kind: ErrorKind::Resolve,
+                start: 0,
+                end: 11,
+                error: Error::NoSuchWeekday(5, Weekday::Friday),
+            })
+        );
+        assert_eq!(
+            error("now+1d"),
+            Ok("-262143-01-02T00:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn between() {
+        let now = base(&Local);
+
+        assert_eq!(
+            duration_between("now+1d", "now+3d", now),
+            Ok(TimeComponents {
+                days: 2,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            duration_between("now/d", "now", now),
+            Ok(TimeComponents {
+                hours: 5,
+                minutes: 40,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            duration_between("now", "now+now", now),
+            Err(Error::MultipleNow(4))
+        );
+    }
+
+    #[test]
+    fn now_ago() {
+        test_input_string!("now 3d ago", "2023-08-18T05:40:00");
+        test_input_string!("now+1y 3d ago", "2024-08-18T05:40:00");
+    }
+
+    #[test]
+    fn weekday_next() {
+        // the fixture is a monday
+        test_input_string!("now next mon", "2023-08-28T05:40:00");
+        test_input_string!("now next tue", "2023-08-22T05:40:00");
+        test_input_string!("now next sun", "2023-08-27T05:40:00");
+    }
+
+    #[test]
+    fn weekday_last() {
+        test_input_string!("now last mon", "2023-08-14T05:40:00");
+        test_input_string!("now last sun", "2023-08-20T05:40:00");
+        test_input_string!("now last tue", "2023-08-15T05:40:00");
+    }
+
+    #[test]
+    fn weekday_this() {
+        test_input_string!("now mon", "2023-08-21T05:40:00");
+        test_input_string!("now this mon", "2023-08-21T05:40:00");
+        test_input_string!("now this fri", "2023-08-25T05:40:00");
+        test_input_string!("now sun", "2023-08-27T05:40:00");
+    }
+
+    #[test]
+    fn weekday_combined() {
+        test_input_string!("now next fri/d+9h", "2023-08-25T09:00:00");
+        test_input_string!("now+1d next mon", "2023-08-28T05:40:00");
+        test_input_string!("now-1d mon", "2023-08-21T05:40:00");
+    }
+
+    #[test]
+    fn week_at_weekday() {
+        // the fixture is a monday, the weekday is taken within the week
+        test_input_string!("now+1w@fri", "2023-09-01T00:00:00");
+        test_input_string!("now+1w@mon", "2023-08-28T00:00:00");
+        test_input_string!("now+2w@sun+9h", "2023-09-10T09:00:00");
+        test_input_string!("now-1w@wed", "2023-08-16T00:00:00");
+        test_input_string!("now+3d+1w@mon", "2023-08-28T00:00:00");
+    }
+
+    #[test]
+    fn floor_weekday() {
+        // the fixture is a monday, two days later is a wednesday
+        test_input_string!("now+2d/mon", "2023-08-21T00:00:00");
+        test_input_string!("now+2d/sun", "2023-08-20T00:00:00");
+        test_input_string!("now+2d/wed", "2023-08-23T00:00:00");
+        test_input_string!("now+2d/thu", "2023-08-17T00:00:00");
+        test_input_string!("now/mon", "2023-08-21T00:00:00");
+    }
+
+    #[test]
+    fn floor_checked() {
+        let midnight = Local
+            .with_ymd_and_hms(2023, 8, 21, 0, 0, 0)
+            .single()
+            .expect("input date time ambiguous");
+
+        assert_eq!(midnight.floor_checked(Unit::Day), Ok((midnight, false)));
+        assert_eq!(midnight.floor_checked(Unit::Week), Ok((midnight, false)));
+        assert_eq!(
+            midnight.floor_checked(Unit::Month).map(|(_, moved)| moved),
+            Ok(true)
+        );
+        let (floored, moved) = (midnight + chrono::TimeDelta::minutes(330))
+            .floor_checked(Unit::Hour)
+            .expect("input date time invalid");
+        assert!(moved);
+        assert_eq!(floored.floor_checked(Unit::Hour), Ok((floored, false)));
+    }
+
+    #[test]
+    fn floor_weekday_exclusive() {
+        let options = ParseOptions {
+            floor_exclusive: true,
+            ..Default::default()
+        };
+        let midnight = Local
+            .with_ymd_and_hms(2023, 8, 21, 0, 0, 0)
+            .single()
+            .expect("input date time ambiguous");
+
+        assert_eq!(
+            parse_str_with_options("now/mon", midnight, options),
+            midnight.sub_weeks(1)
+        );
+        assert_eq!(
+            parse_str_with_options("now/sun", midnight, options),
+            midnight.sub_days(1)
+        );
+        assert_eq!(
+            parse_str_with_options("/mon+now", midnight, options),
+            Err(Error::FloorBeforeNow)
+        );
+    }
+
+    #[test]
+    fn weekday_unsupported() {
+        assert_eq!(
+            parse_str_with_now("now next mon", std::time::Duration::ZERO),
+            Err(Error::UnsupportedExpression(Expression::Weekday(
+                Qualifier::Next,
+                Weekday::Monday
+            )))
+        );
+        assert_eq!(
+            parse_str_with_now("now/fri", std::time::Duration::ZERO),
+            Err(Error::UnsupportedExpression(Expression::FloorWeekday(
+                Weekday::Friday
+            )))
+        );
+        assert_eq!(
+            parse_seconds("now last fri"),
+            Err(Error::UnsupportedExpression(Expression::Weekday(
+                Qualifier::Last,
+                Weekday::Friday
+            )))
+        );
+    }
+
+    #[test]
+    fn overflowing_units() {
+        let now = Local::now();
+
+        assert_eq!(
+            parse_str_with_now("now+4294967295y", now),
+            Err(Error::InvalidDelta)
+        );
+        assert_eq!(
+            parse_str_with_now("now-4294967295w", now),
+            Err(Error::InvalidDelta)
+        );
+        assert_eq!(
+            parse_str_with_now("now+4294967295h", now),
+            Err(Error::InvalidDelta)
+        );
+        assert_eq!(
+            parse_str_with_now("now+400000000d", std::time::Duration::ZERO),
+            Err(Error::InvalidDelta)
+        );
+    }
+
+    #[test]
+    fn leading_now_lenient() {
+        test_input_string!("+1s+now", "2023-08-21T05:40:01");
+    }
+
+    #[test]
+    fn leading_now_strict() {
+        let options = ParseOptions {
+            require_leading_now: true,
+            ..Default::default()
+        };
+        let now = Local::now();
+
+        assert_eq!(
+            parse_str_with_options("+1s+now", now, options),
+            Err(Error::NowNotFirst(0))
+        );
+        assert_eq!(
+            parse_str_with_options("  -1d+now", now, options),
+            Err(Error::NowNotFirst(2))
+        );
+        assert_eq!(
+            parse_str_with_options("now-1d+1s", now, options),
+            now.sub_days(1).and_then(|time| time.add_seconds(1))
+        );
+    }
+
+    #[test]
+    fn max_magnitude() {
+        let options = ParseOptions {
+            max_magnitude: Some((10, Unit::Year)),
+            ..Default::default()
+        };
+        let now = Local::now();
+        let parse = |text| parse_str_with_options(text, now, options);
+
+        assert_eq!(parse("now+9y"), now.add_years(9));
+        assert_eq!(parse("now+10y"), now.add_years(10));
+        assert_eq!(parse("now+120M"), now.add_months(120));
+        assert_eq!(
+            parse("now+11y"),
+            Err(Error::OffsetTooLarge(3, Unit::Year, 11))
+        );
+        assert_eq!(
+            parse("now+1d 11y ago"),
+            Err(Error::OffsetTooLarge(7, Unit::Year, 11))
+        );
+        assert_eq!(
+            parse("now-121M"),
+            Err(Error::OffsetTooLarge(3, Unit::Month, 121))
+        );
+        assert_eq!(
+            parse("-4000d+now"),
+            Err(Error::OffsetTooLarge(0, Unit::Day, 4000))
+        );
+        assert_eq!(
+            parse("now+4294967295s"),
+            Err(Error::OffsetTooLarge(3, Unit::Second, 4_294_967_295))
+        );
+    }
+
+    #[test]
+    fn month_add_sticky_eom() {
+        let sticky = ParseOptions {
+            month_add_sticky_eom: true,
+            ..Default::default()
+        };
+        let parse = |text, (year, month, day), options| {
+            let now = Local
+                .with_ymd_and_hms(year, month, day, 5, 40, 0)
+                .single()
+                .expect("input date time ambiguous");
+            parse_str_with_options(text, now, options)
+                .map(|res| format!("{}", res.format("%Y-%m-%dT%H:%M:%S")))
+        };
+        let ok = |text: &str| Ok(text.to_string());
+
+        assert_eq!(
+            parse("now+1M", (2023, 1, 31), sticky),
+            ok("2023-02-28T05:40:00")
+        );
+        assert_eq!(
+            parse("now+1M+1M", (2023, 1, 31), sticky),
+            ok("2023-03-31T05:40:00")
+        );
+        assert_eq!(
+            parse("now+1M+1M", (2023, 1, 31), ParseOptions::default()),
+            ok("2023-03-28T05:40:00")
+        );
+        assert_eq!(
+            parse("now-1M", (2023, 4, 30), sticky),
+            ok("2023-03-31T05:40:00")
+        );
+        assert_eq!(
+            parse("now+1y", (2023, 2, 28), sticky),
+            ok("2024-02-29T05:40:00")
+        );
+        // not the end of the month in a leap year
+        assert_eq!(
+            parse("now+1M", (2024, 2, 28), sticky),
+            ok("2024-03-28T05:40:00")
+        );
+        let spill = ParseOptions {
+            month_overflow: MonthOverflow::Spill,
+            ..sticky
+        };
+        assert_eq!(
+            parse("now+1M", (2023, 1, 31), spill),
+            ok("2023-02-28T05:40:00")
+        );
+        assert_eq!(
+            parse("now+1M", (2023, 1, 30), spill),
+            ok("2023-03-02T05:40:00")
+        );
+    }
+
+    #[test]
+    fn month_overflow() {
+        let spill = ParseOptions {
+            month_overflow: MonthOverflow::Spill,
+            ..Default::default()
+        };
+        let parse = |text, (year, month, day), options| {
+            let now = Local
+                .with_ymd_and_hms(year, month, day, 5, 40, 0)
+                .single()
+                .expect("input date time ambiguous");
+            parse_str_with_options(text, now, options)
+                .map(|res| format!("{}", res.format("%Y-%m-%dT%H:%M:%S")))
+        };
+        let ok = |text: &str| Ok(text.to_string());
+
+        let clamp = ParseOptions::default();
+        assert_eq!(
+            parse("now+1M", (2023, 1, 31), clamp),
+            ok("2023-02-28T05:40:00")
+        );
+        assert_eq!(
+            parse("now+1M", (2023, 1, 31), spill),
+            ok("2023-03-03T05:40:00")
+        );
+        assert_eq!(
+            parse("now+1M", (2024, 1, 31), clamp),
+            ok("2024-02-29T05:40:00")
+        );
+        assert_eq!(
+            parse("now+1M", (2024, 1, 31), spill),
+            ok("2024-03-02T05:40:00")
+        );
+        assert_eq!(
+            parse("now-1M", (2023, 3, 31), spill),
+            ok("2023-03-03T05:40:00")
+        );
+        assert_eq!(
+            parse("now+1y", (2024, 2, 29), clamp),
+            ok("2025-02-28T05:40:00")
+        );
+        assert_eq!(
+            parse("now+1y", (2024, 2, 29), spill),
+            ok("2025-03-01T05:40:00")
+        );
+        assert_eq!(
+            parse("now+1M", (2023, 1, 15), spill),
+            ok("2023-02-15T05:40:00")
+        );
+        assert_eq!(
+            parse_str_with_options("now+1M", std::time::Duration::ZERO, spill),
+            Err(Error::UnsupportedExpression(Expression::Add(
+                1,
+                Unit::Month
+            )))
+        );
+    }
+
+    #[test]
+    fn seconds() {
+        assert_eq!(parse_seconds("now"), Ok(0));
+        assert_eq!(parse_seconds("now+90m"), Ok(5400));
+        assert_eq!(parse_seconds("now-1d"), Ok(-86400));
+        assert_eq!(parse_seconds("-1w+now+1s"), Ok(-604799));
+        assert_eq!(parse_seconds("now 2h ago"), Ok(-7200));
+    }
+
+    #[test]
+    fn seconds_unsupported() {
+        assert_eq!(parse_seconds("+1d"), Err(Error::MissingNow));
+        assert_eq!(parse_seconds("now+now"), Err(Error::MultipleNow(4)));
+        assert_eq!(
+            parse_seconds("now+1M"),
+            Err(Error::UnsupportedUnit(Unit::Month))
+        );
+        assert_eq!(
+            parse_seconds("now-1y"),
+            Err(Error::UnsupportedUnit(Unit::Year))
+        );
+        assert_eq!(
+            parse_seconds("now/d"),
+            Err(Error::UnsupportedUnit(Unit::Day))
+        );
+    }
+
+    #[test]
+    fn apply_str_chains() {
+        let base = base(&Local);
+
+        assert_eq!(
+            apply_str(base, "+1d").and_then(|time| apply_str(time, "-2h")),
+            parse_str_with_now("now+1d-2h", base)
+        );
+        assert_eq!(
+            apply_str(base, "/d+1h"),
+            parse_str_with_now("now/d+1h", base)
+        );
+        assert_eq!(apply_str(base, "3d ago"), base.sub_days(3));
+        assert_eq!(
+            apply_str(base, "next fri"),
+            parse_str_with_now("now next fri", base)
+        );
+        assert_eq!(apply_str(base, "now+1d"), base.add_days(1));
+        assert_eq!(apply_str(base, "1d+now"), base.add_days(1));
+        assert_eq!(apply_str(base, ""), Ok(base));
+        assert_eq!(
+            apply_str(base, "+1x"),
+            Err(Error::UnexpectedCharacter(2, 'x'))
+        );
+        assert_eq!(apply_str(base, "+1d+now+now"), Err(Error::MultipleNow(8)));
+    }
+
+    #[test]
+    fn or_default() {
+        let now = std::time::Duration::from_secs(1 << 20);
+
+        for text in [None, Some(""), Some("  ")] {
+            assert_eq!(
+                parse_str_or_default(text, "now-1d", now),
+                parse_str_with_now("now-1d", now),
+                "{text:?}"
+            );
+        }
+        assert_eq!(
+            parse_str_or_default(Some("now+2h"), "now-1d", now),
+            parse_str_with_now("now+2h", now)
+        );
+        assert_eq!(
+            parse_str_or_default(Some("now+2x"), "now-1d", now),
+            Err(Error::UnexpectedCharacter(5, 'x'))
+        );
+        assert_eq!(parse_str_or_default(None, "", now), Err(Error::MissingNow));
+    }
+
+    #[test]
+    fn pure_offset() {
+        let base = base(&Local);
+
+        assert_eq!(parse_pure_offset_str("+30m", base), base.add_minutes(30));
+        assert_eq!(parse_pure_offset_str("30m", base), base.add_minutes(30));
+        assert_eq!(
+            parse_pure_offset_str("1h ago-15m", base),
+            base.sub_minutes(75)
+        );
+        assert_eq!(
+            parse_pure_offset_str("now+30m", base),
+            Err(Error::NowNotAllowed(0))
+        );
+        assert_eq!(
+            parse_pure_offset_str("+30m+now", base),
+            Err(Error::NowNotAllowed(5))
+        );
+        assert_eq!(
+            parse_pure_offset_str("epoch", base),
+            Err(Error::NowNotAllowed(0))
+        );
+        assert_eq!(
+            parse_pure_offset_str("+30m/h", base),
+            Err(Error::UnsupportedExpression(Expression::Floor(Unit::Hour)))
+        );
+        assert_eq!(parse_pure_offset_str("", base), Err(Error::NoExpressions));
+    }
+
+    #[test]
+    fn describe_terms() {
+        for (text, description) in [
+            ("now+1d+2h", "1 day and 2 hours from the base time"),
+            (
+                "now-1d+2h",
+                "1 day before the base time, then 2 hours later",
+            ),
+            (
+                "now+1d-2h",
+                "1 day from the base time, then 2 hours earlier",
+            ),
+            ("now/w", "the base time, snapped to the start of the week"),
+            (
+                "now-1M/M+0d+1w",
+                "1 month before the base time, snapped to the start of the month, then 1 week later",
+            ),
+            ("now+0d", "the base time"),
+            ("now+0d-0h+5m", "5 minutes from the base time"),
+            (
+                "epoch+1y+2M+3d",
+                "1 year, 2 months and 3 days from the unix epoch",
+            ),
+            ("now next mon", "the base time, then next monday"),
+            (
+                "now/Y",
+                "the base time, snapped to the start of the ISO year",
+            ),
+            (
+                "now|h",
+                "the base time, checked to be at the start of the hour",
+            ),
+            (
+                "now+1M/3thu",
+                "1 month from the base time, snapped to thursday number 3 of the month",
+            ),
+            (
+                "now+1h~5m",
+                "1 hour from the base time, give or take up to 5 minutes",
+            ),
+        ] {
+            assert_eq!(describe(text), Ok(description.to_string()), "{text}");
+        }
+        assert_eq!(describe("+1d"), Err(Error::MissingNow));
+        assert_eq!(describe("now+1d+now"), Err(Error::MultipleNow(7)));
+        assert_eq!(describe("/d now"), Err(Error::FloorBeforeNow));
+    }
+
+    #[test]
+    fn resolve_built_exprs() {
+        let base = base(&Local);
+        let exprs = vec![
+            Expression::Now(0),
+            Expression::Add(1, Unit::Month),
+            Expression::Floor(Unit::Month),
+            Expression::Weekday(Qualifier::Next, Weekday::Monday),
+            Expression::Sub(2, Unit::Hour),
+        ];
+
+        assert_eq!(
+            resolve_exprs(base, exprs),
+            parse_str_with_now("now+1M/M next mon-2h", base)
+        );
+        assert_eq!(
+            resolve_exprs(base, [Expression::Sub(1, Unit::Day), Expression::Now(0)]),
+            base.sub_days(1)
+        );
+        assert_eq!(
+            resolve_exprs(base, [Expression::Add(1, Unit::Day)]),
+            Err(Error::MissingNow)
+        );
+        assert_eq!(
+            resolve_exprs(base, [Expression::Now(0), Expression::Epoch(1)]),
+            Err(Error::MultipleNow(1))
+        );
+    }
+
+    #[test]
+    fn overflow_at() {
+        let now = Local::now();
+        assert_eq!(
+            parse_str_with_now("now+4000000000d", now),
+            Err(Error::OverflowAt(
+                3,
+                Expression::Add(4_000_000_000, Unit::Day)
+            ))
+        );
+        assert_eq!(
+            parse_str_with_now("now/d+1d 4000000000d ago", now),
+            Err(Error::OverflowAt(
+                9,
+                Expression::Sub(4_000_000_000, Unit::Day)
+            ))
+        );
+    }
+
+    #[test]
+    fn net_apply() {
+        let options = ParseOptions {
+            net_apply: true,
+            ..Default::default()
+        };
+        let now = base(&Local);
+
+        assert_eq!(
+            parse_str_with_now("now+1000000000d-999999999d", now),
+            Err(Error::OverflowAt(
+                3,
+                Expression::Add(1_000_000_000, Unit::Day)
+            ))
+        );
+        assert_eq!(
+            parse_str_with_options("now+1000000000d-999999999d", now, options),
+            now.add_days(1)
+        );
+        assert_eq!(
+            parse_str_with_options("now+1d/d-1d+3h 1d ago", now, options),
+            now.add_days(1)
+                .and_then(RelativeTime::floor_days)
+                .and_then(|time| time.add_hours(3))
+                .and_then(|time| time.sub_days(2)),
+            "floors are barriers"
+        );
+        assert_eq!(
+            parse_str_with_options("now+4294967295s+1s", now, options),
+            Err(Error::InvalidDelta)
+        );
+
+        // an overflow is reported like without summing
+        let max = DateTime::<chrono::Utc>::MAX_UTC;
+        assert_eq!(
+            parse_str_with_options("now+1d", max, options),
+            Err(Error::OverflowAt(3, Expression::Add(1, Unit::Day)))
+        );
+        assert_eq!(
+            parse_str_with_options("now+1d", max, options),
+            parse_str_with_now("now+1d", max)
+        );
+        assert_eq!(
+            parse_str_with_options("now-1h+2d+1h", max, options),
+            Err(Error::OverflowAt(6, Expression::Add(2, Unit::Day)))
+        );
+    }
+
+    #[test]
+    fn strict_case() {
+        let options = ParseOptions {
+            strict_case: true,
+            ..Default::default()
+        };
+        let now = std::time::Duration::from_secs(1 << 20);
+        assert_eq!(
+            parse_str_with_options("now - 1D", now, options),
+            Err(Error::WrongCaseUnit(7, 'd'))
+        );
+        assert_eq!(
+            parse_str_with_options("now - 1D", now, ParseOptions::default()),
+            Err(Error::UnexpectedCharacter(7, 'D'))
+        );
+        assert_eq!(
+            Error::WrongCaseUnit(7, 'd').to_string(),
+            "unit 'd' is lowercase, found an uppercase unit at position 7"
+        );
+    }
+
+    #[test]
+    fn allow_comments() {
+        let options = ParseOptions {
+            allow_comments: true,
+            ..Default::default()
+        };
+        let now = std::time::Duration::from_secs(1 << 20);
+        assert_eq!(
+            parse_str_with_options("now+1d # tomorrow", now, options),
+            parse_str_with_now("now+1d", now)
+        );
+        assert_eq!(
+            parse_str_with_options("now # just now", now, options),
+            Ok(now)
+        );
+        assert_eq!(
+            parse_str_with_options("# now", now, options),
+            Err(Error::MissingNow)
+        );
+        assert_eq!(
+            parse_str_with_options("now+1d # tomorrow", now, ParseOptions::default()),
+            Err(Error::UnexpectedCharacter(7, '#'))
+        );
+    }
+
+    #[test]
+    fn leading_floor() {
+        let now = std::time::Duration::ZERO;
+        for text in ["/w", "/w+now", " / w + now", "/mon+now"] {
+            assert_eq!(
+                parse_str_with_now(text, now),
+                Err(Error::FloorBeforeNow),
+                "{text}"
+            );
+            assert_eq!(parse_seconds(text), Err(Error::FloorBeforeNow), "{text}");
+        }
+        assert_eq!(
+            parse_str_with_now("+1d/w+now", now),
+            Err(Error::FloorBeforeNow)
+        );
+    }
+
+    #[test]
+    fn floor_inclusive() {
+        test_input_string!("now/d/d", "2023-08-21T00:00:00");
+        test_input_string!("now/d+1h/h/d", "2023-08-21T00:00:00");
+    }
+
+    #[test]
+    fn floor_exclusive() {
+        let options = ParseOptions {
+            floor_exclusive: true,
+            ..Default::default()
+        };
+        let midnight = Local
+            .with_ymd_and_hms(2023, 8, 21, 0, 0, 0)
+            .single()
+            .expect("input date time ambiguous");
+        let parse = |text| {
+            parse_str_with_options(text, midnight, options)
+                .map(|res| format!("{}", res.format("%Y-%m-%dT%H:%M:%S")))
+        };
+
+        assert_eq!(parse("now/d"), Ok("2023-08-20T00:00:00".to_string()));
+        assert_eq!(parse("now/h"), Ok("2023-08-20T23:00:00".to_string()));
+        assert_eq!(parse("now/M"), Ok("2023-08-01T00:00:00".to_string()));
+        assert_eq!(parse("now+1s/d"), Ok("2023-08-21T00:00:00".to_string()));
+    }
+
+    #[test]
+    fn assert_floor() {
+        let at = |hour, minute| {
+            Local
+                .with_ymd_and_hms(2023, 8, 21, hour, minute, 0)
+                .single()
+                .expect("input date time ambiguous")
+        };
+
+        assert_eq!(parse_str_with_now("now|h", at(5, 0)), Ok(at(5, 0)));
+        assert_eq!(parse_str_with_now("now|d", at(0, 0)), Ok(at(0, 0)));
+        assert_eq!(parse_str_with_now("now+20m|h", at(5, 40)), Ok(at(6, 0)));
+        assert_eq!(
+            parse_str_with_now("now|h", at(5, 40)),
+            Err(Error::AlreadyPastBoundary(3, Unit::Hour))
+        );
+        assert_eq!(
+            parse_str_with_now("now+1d|d", at(5, 0)),
+            Err(Error::AlreadyPastBoundary(6, Unit::Day))
+        );
+        // the boundary itself passes, also when floors are exclusive
+        let options = ParseOptions {
+            floor_exclusive: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_str_with_options("now|d", at(0, 0), options),
+            Ok(at(0, 0))
+        );
+        assert_eq!(
+            parse_str_with_now("|d+now", at(0, 0)),
+            Err(Error::FloorBeforeNow)
+        );
+    }
+
+    #[test]
+    fn reject_subday_floor() {
+        let options = ParseOptions {
+            reject_subday_floor: true,
+            ..Default::default()
+        };
+        let now = Local::now();
+
+        for (text, unit) in [
+            ("now/h", Unit::Hour),
+            ("now-1d/m", Unit::Minute),
+            ("now/d+1h/s", Unit::Second),
+            ("now/ms", Unit::Millisecond),
+            ("now/q", Unit::Minute),
+        ] {
+            assert_eq!(
+                parse_str_with_options(text, now, options),
+                Err(Error::UnsupportedFloor(unit))
+            );
+            assert!(parse_str_with_now(text, now).is_ok(), "{text}");
+        }
+        assert_eq!(
+            parse_str_with_options("now-1d/d+2h", now, options),
+            parse_str_with_now("now-1d/d+2h", now)
+        );
+        assert!(parse_str_with_options("now/w", now, options).is_ok());
+    }
+
+    #[test]
+    fn business_hours() {
+        assert_eq!(
+            BusinessHours::new((9, 0), (17, 30)).map(|hours| (hours.open(), hours.close())),
+            Ok(((9, 0), (17, 30)))
+        );
+        for (open, close) in [
+            ((17, 0), (9, 0)),
+            ((9, 0), (9, 0)),
+            ((9, 0), (24, 0)),
+            ((9, 60), (17, 0)),
+        ] {
+            assert_eq!(
+                BusinessHours::new(open, close),
+                Err(Error::InvalidBusinessHours)
+            );
+        }
+        // without a wall clock there are no business hours to floor to
+        let options = ParseOptions {
+            business_hours: BusinessHours::new((9, 0), (17, 0)).ok(),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_str_with_options("now/bh", std::time::Duration::ZERO, options),
+            Err(Error::UnsupportedExpression(Expression::FloorBusinessHours))
+        );
+    }
+
+    #[test]
+    fn week_floor_clamp_to_month() {
+        let options = ParseOptions {
+            week_floor_clamp_to_month: true,
+            ..Default::default()
+        };
+        let parse = |text, day, options| {
+            let now = Local
+                .with_ymd_and_hms(2023, 11, day, 5, 40, 0)
+                .single()
+                .expect("input date time ambiguous");
+            parse_str_with_options(text, now, options)
+                .map(|res| format!("{}", res.format("%Y-%m-%dT%H:%M:%S")))
+        };
+
+        // 2023-11-01 is a wednesday, its week starts in october
+        assert_eq!(
+            parse("now/w", 1, ParseOptions::default()),
+            Ok("2023-10-30T00:00:00".to_string())
+        );
+        assert_eq!(
+            parse("now/w", 1, options),
+            Ok("2023-11-01T00:00:00".to_string())
+        );
+        assert_eq!(
+            parse("now/w", 9, options),
+            Ok("2023-11-06T00:00:00".to_string())
+        );
+        assert_eq!(
+            parse("now-1d/w", 1, options),
+            Ok("2023-10-30T00:00:00".to_string()),
+            "clamps to the month of the floored time"
+        );
+    }
+
+    #[test]
+    fn week_modes() {
+        use chrono::{NaiveDate, Utc};
+
+        let utc = |year, month, day| {
+            Utc.with_ymd_and_hms(year, month, day, 12, 0, 0)
+                .single()
+                .expect("input date time invalid")
+        };
+        let midnight = |year, month: u8, day: u8| {
+            NaiveDate::from_ymd_opt(year, month.into(), day.into()).expect("input date invalid")
+        };
+        // 2022-08-24 is a wednesday, 2022-01-01 a saturday
+        let now = utc(2022, 8, 24);
+        for (week_mode, (year, month, day)) in [
+            (WeekMode::EpochAligned, (2022, 8, 18)),
+            (WeekMode::IsoMonday, (2022, 8, 22)),
+            (WeekMode::CalendarSunday, (2022, 8, 21)),
+            (WeekMode::YearRelative, (2022, 8, 20)),
+        ] {
+            let options = ParseOptions {
+                week_mode,
+                ..Default::default()
+            };
+            let time = parse_str_with_options("now/w", now, options);
+            assert_eq!(
+                time.map(|res| res.date_naive()),
+                Ok(midnight(year, month, day)),
+                "{week_mode:?}"
+            );
+
+            #[cfg(feature = "nanos")]
+            {
+                let nanos = now.timestamp_nanos_opt().map(i128::from);
+                let time = time.map(|res| res.timestamp_nanos_opt().map(i128::from));
+                assert_eq!(
+                    nanos.map(|nanos| parse_str_with_options("now/w", nanos, options)),
+                    time.transpose(),
+                    "{week_mode:?}"
+                );
+            }
+
+            #[cfg(feature = "jiff")]
+            {
+                let now = jiff::civil::date(2022, 8, 24).at(12, 0, 0, 0);
+                assert_eq!(
+                    parse_str_with_options("now/w", now, options).map(|res| res.date()),
+                    Ok(jiff::civil::date(
+                        year.try_into().expect("year should fit"),
+                        month.try_into().expect("month should fit"),
+                        day.try_into().expect("day should fit"),
+                    )),
+                    "{week_mode:?}"
+                );
+            }
+
+            #[cfg(feature = "icu")]
+            {
+                let date = |year, month, day| {
+                    icu_calendar::Date::try_new_gregorian_date(year, month, day)
+                        .expect("input date invalid")
+                };
+                assert_eq!(
+                    parse_str_with_options("now/w", date(2022, 8, 24), options),
+                    Ok(date(year, month, day)),
+                    "{week_mode:?}"
+                );
+            }
+        }
+
+        // the last week of 2021 is only the friday 2021-12-31
+        let options = ParseOptions {
+            week_mode: WeekMode::YearRelative,
+            floor_exclusive: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_str_with_options("now/d/w", utc(2022, 1, 1), options).map(|res| res.date_naive()),
+            Ok(midnight(2021, 12, 31))
+        );
+        assert_eq!(
+            parse_str_with_options("now/w", std::time::Duration::ZERO, options),
+            Err(Error::UnsupportedUnit(Unit::Week))
+        );
+    }
+
+    #[test]
+    fn past_2038() {
+        use chrono::Utc;
+
+        // the last second of signed 32-bit unix timestamps
+        let last = i64::from(i32::MAX);
+        #[cfg(feature = "nanos")]
+        assert_eq!(
+            parse_str_with_now("now+1s", i128::from(last) * 1_000_000_000),
+            Ok(i128::from(last + 1) * 1_000_000_000)
+        );
+        let seconds = u64::try_from(last).expect("seconds should fit");
+        assert_eq!(
+            parse_str_with_now("now+1s", std::time::Duration::from_secs(seconds)),
+            Ok(std::time::Duration::from_secs(seconds + 1))
+        );
+
+        let now = base(&Utc);
+        assert_eq!(
+            parse_str_with_now("now+30y", now).map(|res| res.to_rfc3339()),
+            Ok("2053-08-21T05:40:00+00:00".to_string())
+        );
+        for text in ["now+30y", "now+15y-2M/d", "now+14y+5M-2d", "now+800w/M"] {
+            let time = parse_str_with_now(text, now).expect("resolving failed");
+            assert!(time.timestamp() > last, "{text}");
+
+            #[cfg(feature = "nanos")]
+            {
+                let nanos = now.timestamp_nanos_opt().map(i128::from);
+                assert_eq!(
+                    nanos.map(|nanos| parse_str_with_now(text, nanos)),
+                    time.timestamp_nanos_opt().map(|res| Ok(i128::from(res))),
+                    "{text}"
+                );
+            }
+
+            #[cfg(feature = "db")]
+            assert_eq!(
+                parse_str_with_now(text, now.naive_utc()),
+                Ok(time.naive_utc()),
+                "{text}"
+            );
+
+            #[cfg(feature = "bson")]
+            assert_eq!(
+                parse_str_with_now(text, bson::DateTime::from_chrono(now)),
+                Ok(bson::DateTime::from_chrono(time)),
+                "{text}"
+            );
+
+            #[cfg(feature = "jiff")]
+            assert_eq!(
+                parse_str_with_now(text, jiff::civil::date(2023, 8, 21).at(5, 40, 0, 0))
+                    .map(|res| res.to_string()),
+                Ok(time.naive_utc().format("%Y-%m-%dT%H:%M:%S").to_string()),
+                "{text}"
+            );
+
+            #[cfg(feature = "icu")]
+            {
+                use chrono::Datelike;
+
+                let date = icu_calendar::Date::try_new_gregorian_date(2023, 8, 21)
+                    .expect("input date invalid");
+                assert_eq!(
+                    parse_str_with_now(text, date).map(|res| {
+                        let iso = res.to_iso();
+                        (iso.year().number, iso.month().ordinal, iso.day_of_month().0)
+                    }),
+                    Ok((time.year(), time.month(), time.day())),
+                    "{text}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn earliest_and_latest() {
+        let now = Local::now();
+
+        assert_eq!(earliest(&["now+1d", "now-1d"], now), now.sub_days(1));
+        assert_eq!(latest(&["now+1d", "now-1d"], now), now.add_days(1));
+        assert_eq!(
+            earliest(&["now", "now+1x"], now),
+            Err(Error::UnexpectedCharacter(5, 'x'))
+        );
+        assert_eq!(earliest(&[], now), Err(Error::NoExpressions));
+    }
+
+    // Every sequence of up to four of these pieces, against times at the ends
+    // of their ranges. Any panic fails the test.
+    #[test]
+    fn never_panics() {
+        const PIECES: [&str; 20] = [
+            "now",
+            "epoch",
+            "+",
+            "-",
+            "/",
+            "1",
+            "4294967295",
+            "y",
+            "Y",
+            "M",
+            "w",
+            "d",
+            "h",
+            "ms",
+            "s",
+            "next",
+            "mon",
+            "ago",
+            "@",
+            " ",
+        ];
+        let offset = |seconds| chrono::FixedOffset::east_opt(seconds).expect("offset invalid");
+        let chrono_nows = [
+            DateTime::<chrono::Utc>::MIN_UTC.with_timezone(&offset(-86_399)),
+            DateTime::<chrono::Utc>::MAX_UTC.with_timezone(&offset(86_399)),
+            DateTime::UNIX_EPOCH.with_timezone(&offset(0)),
+        ];
+
+        let mut indices: Vec<usize> = Vec::new();
+        loop {
+            let text: String = indices.iter().map(|&index| PIECES[index]).collect();
+            for now in chrono_nows {
+                let _ = parse_str_with_now(&text, now);
+                let _ = parse_str_delta(&text, now);
+            }
+            #[cfg(feature = "nanos")]
+            for now in [i128::MIN, 0, i128::MAX] {
+                let _ = parse_str_with_now(&text, now);
+            }
+            let _ = parse_bytes_with_now(text.as_bytes(), std::time::Duration::ZERO);
+            let _ = parse_seconds(&text);
+            let _ = describe(&text);
+
+            // the next sequence, counting in base `PIECES.len()`
+            match indices.iter().rposition(|&index| index + 1 < PIECES.len()) {
+                Some(position) => {
+                    indices[position] += 1;
+                    indices[position + 1..].fill(0);
+                }
+                None if indices.len() < 4 => indices = vec![0; indices.len() + 1],
+                None => break,
+            }
+        }
+    }
 }
@@ -0,0 +1,319 @@
+//! Compile time checks for literal expressions, see [`rel_time!`](crate::rel_time).
+
+/// Parses a literal expression, like [`parse_str`](crate::parse_str), or like
+/// [`parse_str_with_now`](crate::parse_str_with_now) when given a `now`.
+///
+/// The literal is checked while compiling, so `rel_time!("+1d")` fails to build
+/// as it misses `now`. Only the shape of the expression is checked, with these
+/// limitations:
+///
+/// - only `now` or `epoch`, `+`/`-` terms with an optional `*` count and
+///   `ago`, terms with only `ago`, `~` jitter terms, `|` on a unit and `/`
+///   floors on a unit or to `Y`, `q`, `H` and `bh` are accepted;
+/// - nothing with a weekday is accepted, so neither weekday terms, `@weekday`
+///   after a week term nor floors like `/3thu`;
+/// - only ASCII whitespace is accepted;
+/// - results can still be out of range or not supported by the type, so the
+///   macro evaluates to a [`Result`](crate::Result).
+///
+/// ```
+/// use std::time::Duration;
+///
+/// let time = relative_time_str::rel_time!("now+1d-2h", Duration::ZERO);
+/// assert_eq!(time, Ok(Duration::from_secs(22 * 60 * 60)));
+/// ```
+#[macro_export]
+macro_rules! rel_time {
+    ($text:literal) => {{
+        const _: () = ::core::assert!(
+            $crate::macros::is_valid($text),
+            "invalid relative time expression"
+        );
+        $crate::parse_str($text)
+    }};
+    ($text:literal, $now:expr) => {{
+        const _: () = ::core::assert!(
+            $crate::macros::is_valid($text),
+            "invalid relative time expression"
+        );
+        $crate::parse_str_with_now($text, $now)
+    }};
+}
+
+/// Whether `text` has the shape of an expression [`rel_time!`](crate::rel_time)
+/// accepts. Not meant to be called directly.
+#[doc(hidden)]
+pub const fn is_valid(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    let mut index = skip_whitespace(bytes, 0);
+    let mut now = false;
+    let mut first = true;
+
+    while index < bytes.len() {
+        let mut implicit = false;
+        match bytes[index] {
+            b'+' | b'-' => index = skip_whitespace(bytes, index + 1),
+            b'/' | b'|' => {
+                let floor = bytes[index] == b'/';
+                index = skip_whitespace(bytes, index + 1);
+                if !now {
+                    return false;
+                }
+                let end = match unit(bytes, index) {
+                    Some(end) => end,
+                    None if floor => match special_floor(bytes, index) {
+                        Some(end) => end,
+                        None => return false,
+                    },
+                    None => return false,
+                };
+                index = skip_whitespace(bytes, end);
+                first = false;
+                continue;
+            }
+            b'~' => {
+                let Some(end) = term(bytes, skip_whitespace(bytes, index + 1)) else {
+                    return false;
+                };
+                index = skip_whitespace(bytes, end);
+                first = false;
+                continue;
+            }
+            b'0'..=b'9' => implicit = !first,
+            b'n' | b'e' if first => {}
+            _ => return false,
+        }
+
+        let keyword = if starts_with(bytes, index, b"now") {
+            3
+        } else if starts_with(bytes, index, b"epoch") {
+            5
+        } else {
+            0
+        };
+        if keyword > 0 {
+            // `now` and `epoch` are added, never subtracted
+            if now || bytes[skip_back(bytes, index)] == b'-' {
+                return false;
+            }
+            now = true;
+            index = skip_whitespace(bytes, index + keyword);
+            first = false;
+            continue;
+        }
+
+        let Some(end) = term(bytes, index) else {
+            return false;
+        };
+        index = skip_whitespace(bytes, end);
+        if index < bytes.len() && bytes[index] == b'*' {
+            let Some(end) = number(bytes, skip_whitespace(bytes, index + 1)) else {
+                return false;
+            };
+            index = skip_whitespace(bytes, end);
+        }
+        if starts_with(bytes, index, b"ago") {
+            index = skip_whitespace(bytes, index + 3);
+        } else if implicit {
+            return false;
+        }
+        first = false;
+    }
+
+    now
+}
+
+// Consumes a number and a unit, returning the index after them.
+const fn term(bytes: &[u8], index: usize) -> Option<usize> {
+    match number(bytes, index) {
+        Some(end) => unit(bytes, skip_whitespace(bytes, end)),
+        None => None,
+    }
+}
+
+// Consumes what `/` floors to besides a unit, returning the index after it.
+const fn special_floor(bytes: &[u8], index: usize) -> Option<usize> {
+    if starts_with(bytes, index, b"bh") {
+        return Some(index + 2);
+    }
+    if index < bytes.len() && matches!(bytes[index], b'Y' | b'q' | b'H') {
+        return Some(index + 1);
+    }
+    None
+}
+
+// Consumes a unit, returning the index after it.
+const fn unit(bytes: &[u8], index: usize) -> Option<usize> {
+    if index >= bytes.len() {
+        return None;
+    }
+    match bytes[index] {
+        b'm' if starts_with(bytes, index, b"ms") || starts_with(bytes, index, b"mo") => {
+            Some(index + 2)
+        }
+        b'y' | b'M' | b'w' | b'd' | b'h' | b'm' | b's' => Some(index + 1),
+        _ => None,
+    }
+}
+
+const fn skip_whitespace(bytes: &[u8], mut index: usize) -> usize {
+    while index < bytes.len() && bytes[index].is_ascii_whitespace() {
+        index += 1;
+    }
+    index
+}
+
+// The index of the last non-whitespace byte before `index`, or `index` itself.
+const fn skip_back(bytes: &[u8], index: usize) -> usize {
+    let mut back = index;
+    while back > 0 {
+        back -= 1;
+        if !bytes[back].is_ascii_whitespace() {
+            return back;
+        }
+    }
+    index
+}
+
+const fn starts_with(bytes: &[u8], index: usize, prefix: &[u8]) -> bool {
+    if bytes.len() - index < prefix.len() {
+        return false;
+    }
+    let mut offset = 0;
+    while offset < prefix.len() {
+        if bytes[index + offset] != prefix[offset] {
+            return false;
+        }
+        offset += 1;
+    }
+    true
+}
+
+// Consumes a number that fits in a `u32`, returning the index after it.
+const fn number(bytes: &[u8], mut index: usize) -> Option<usize> {
+    const MAX: &[u8] = b"4294967295";
+
+    let start = index;
+    while index < bytes.len() && bytes[index] == b'0' {
+        index += 1;
+    }
+    let significant = index;
+    while index < bytes.len() && bytes[index].is_ascii_digit() {
+        index += 1;
+    }
+    if index == start || index - significant > MAX.len() {
+        return None;
+    }
+    if index - significant == MAX.len() {
+        // same amount of digits, so compare them as text
+        let mut offset = 0;
+        while offset < MAX.len() {
+            if bytes[significant + offset] != MAX[offset] {
+                if bytes[significant + offset] > MAX[offset] {
+                    return None;
+                }
+                break;
+            }
+            offset += 1;
+        }
+    }
+    Some(index)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Error, parse_str_with_now};
+
+    fn parses(text: &str) -> bool {
+        let now = std::time::Duration::from_secs(1 << 40);
+        // errors of the type itself do not matter here
+        !matches!(
+            parse_str_with_now(text, now),
+            Err(Error::UnexpectedCharacter(..)
+                | Error::InvalidNumber(..)
+                | Error::InvalidFormat(..)
+                | Error::UnexpectedEndOfInput(..)
+                | Error::FloorBeforeNow
+                | Error::MissingNow
+                | Error::MultipleNow(_))
+        )
+    }
+
+    #[test]
+    fn valid() {
+        for text in [
+            "now",
+            "+now",
+            " now ",
+            "now+1d",
+            "now - 2h + 30m",
+            "now/d+9h",
+            "now-1M/M",
+            "now 3d ago",
+            "now+1y 3d ago",
+            "now-3d ago",
+            "+1s+now",
+            "1s+now",
+            "-5d+now/d",
+            "now+4294967295s",
+            "now+0004294967295s",
+            "now-5ms/ms",
+            "now+1mo+1m/mo",
+            "epoch+1d",
+            "+epoch-1d",
+            "now/Y",
+            "now/q",
+            "now / H",
+            "now/bh",
+            "now|d",
+            "now+15m*4",
+            "now - 1d * 2 ago",
+            "now+1h~5m",
+            "~ 5m+now",
+        ] {
+            assert!(is_valid(text), "{text:?} should be valid");
+            assert!(parses(text), "{text:?} should parse");
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        for text in [
+            "",
+            "+1d",
+            "now+now",
+            "now-now",
+            "-now",
+            "now+",
+            "now/",
+            "now+1",
+            "now+d",
+            "now 3d",
+            "now ago",
+            "/d+now",
+            "now/x",
+            "now+1x",
+            "now+4294967296s",
+            "now+5294967295s",
+            "now+42949672950s",
+            "now next mon",
+            "now/mon",
+            "now+2w@mon",
+            "now/3thu",
+            "now|Y",
+            "now|bh",
+            "now+1d*",
+            "now*2",
+            "now~5m ago",
+            "now~5",
+            "now+epoch",
+            "epoch+now",
+            "now-epoch",
+            "/q+now",
+            "now\u{a0}+1d",
+        ] {
+            assert!(!is_valid(text), "{text:?} should be invalid");
+        }
+    }
+}
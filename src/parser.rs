@@ -1,7 +1,7 @@
-use std::iter::Peekable;
+use std::{collections::VecDeque, iter::Peekable};
 
 use crate::{
-    Error, Result,
+    Error, MONTHS_PER_YEAR, Result,
     error::TokenType,
     lexer::{Lexer, Token},
 };
@@ -16,7 +16,9 @@ macro_rules! ensure_ok {
     };
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Ordered from the largest unit to the smallest, so `Unit::Year` sorts first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Unit {
     Year,
     Month,
@@ -25,6 +27,51 @@ pub enum Unit {
     Hour,
     Minute,
     Second,
+    Millisecond,
+    /// Not written in expressions, but used to truncate results for storage
+    /// with microsecond precision, see `ParseOptions::truncate_to`.
+    Microsecond,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    pub fn num_days_from_monday(self) -> u32 {
+        match self {
+            Self::Monday => 0,
+            Self::Tuesday => 1,
+            Self::Wednesday => 2,
+            Self::Thursday => 3,
+            Self::Friday => 4,
+            Self::Saturday => 5,
+            Self::Sunday => 6,
+        }
+    }
+}
+
+/// Which occurrence of a weekday a weekday term refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Qualifier {
+    /// Today if it is that weekday, otherwise the first one after today. Used
+    /// when a weekday is given without qualifier.
+    This,
+    /// The first one strictly after today, so a week ahead if today is that
+    /// weekday.
+    Next,
+    /// The last one strictly before today, so a week back if today is that
+    /// weekday.
+    Last,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -32,23 +79,74 @@ enum Operator {
     Add,
     Sub,
     Floor,
+    AssertFloor,
+    Jitter,
+    // a term without operator, only valid when followed by `ago`
+    Implicit(usize),
+    // a weekday term, which never has an operator
+    Weekday,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expression {
-    Now,
+    /// `now`, with the position of the keyword in the text.
+    Now(usize),
+    /// The unix epoch, which takes the place of `now`, with the position of the
+    /// keyword in the text.
+    Epoch(usize),
     Add(u32, Unit),
     Sub(u32, Unit),
     Floor(Unit),
+    Weekday(Qualifier, Weekday),
+    FloorWeekday(Weekday),
+    /// Floor to the start of the ISO 8601 week-numbering year, the monday of
+    /// its first week.
+    FloorIsoYear,
+    /// `/bh`, floor to business hours, see `ParseOptions::business_hours`.
+    FloorBusinessHours,
+    /// `|`, which checks that the time is already on the boundary of the unit.
+    AssertFloor(Unit),
+    /// Floor to a multiple of the given minutes within the hour, written as
+    /// `/q` for 15 minutes and `/H` for 30 minutes.
+    FloorMinutes(u32),
+    /// `/3thu`, to the start of the given weekday of the month by its number,
+    /// e.g. the third thursday.
+    NthWeekday(u32, Weekday),
+    /// `~5m`, which moves the time by a random whole number of the unit, up
+    /// to the value in either direction, see `ParseOptions::jitter_seed`.
+    Jitter(u32, Unit),
 }
 
 impl std::fmt::Display for Expression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Now => write!(f, "now"),
+            Self::Now(_) => write!(f, "now"),
+            Self::Epoch(_) => write!(f, "epoch"),
             Self::Add(_, unit) => write!(f, "add {}", unit),
             Self::Sub(_, unit) => write!(f, "subtract {}", unit),
             Self::Floor(unit) => write!(f, "floor {}", unit),
+            Self::Weekday(qualifier, weekday) => write!(f, "{} {}", qualifier, weekday),
+            Self::FloorWeekday(weekday) => write!(f, "floor {}", weekday),
+            Self::FloorIsoYear => write!(f, "floor iso year"),
+            Self::FloorBusinessHours => write!(f, "floor business hours"),
+            Self::AssertFloor(unit) => write!(f, "assert floor {}", unit),
+            Self::FloorMinutes(minutes) => write!(f, "floor {} minutes", minutes),
+            Self::NthWeekday(n, weekday) => write!(f, "floor to {} number {}", weekday, n),
+            Self::Jitter(_, unit) => write!(f, "jitter {}", unit),
+        }
+    }
+}
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Add => write!(f, "+"),
+            Self::Sub => write!(f, "-"),
+            Self::Floor => write!(f, "/"),
+            Self::AssertFloor => write!(f, "|"),
+            Self::Jitter => write!(f, "~"),
+            Self::Implicit(_) | Self::Weekday => Ok(()),
         }
     }
 }
@@ -63,43 +161,216 @@ impl std::fmt::Display for Unit {
             Self::Hour => write!(f, "hour"),
             Self::Minute => write!(f, "minute"),
             Self::Second => write!(f, "second"),
+            Self::Millisecond => write!(f, "millisecond"),
+            Self::Microsecond => write!(f, "microsecond"),
         }
     }
 }
 
+impl std::fmt::Display for Weekday {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Monday => write!(f, "monday"),
+            Self::Tuesday => write!(f, "tuesday"),
+            Self::Wednesday => write!(f, "wednesday"),
+            Self::Thursday => write!(f, "thursday"),
+            Self::Friday => write!(f, "friday"),
+            Self::Saturday => write!(f, "saturday"),
+            Self::Sunday => write!(f, "sunday"),
+        }
+    }
+}
+
+impl std::fmt::Display for Qualifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::This => write!(f, "this"),
+            Self::Next => write!(f, "next"),
+            Self::Last => write!(f, "last"),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Parser<'s> {
     first: bool,
+    // the position reported when the input ends in the middle of a term
+    end: usize,
     tokens: Peekable<Lexer<'s>>,
+    // expressions that follow the last one, with their position
+    pending: VecDeque<(usize, Expression)>,
 }
 
 impl<'s> Parser<'s> {
     pub fn new(text: &'s str) -> Self {
         Self {
             first: true,
+            end: text.len(),
             tokens: Lexer::new(text).peekable(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Parses the tokens of a configured `lexer`.
+    pub fn from_lexer(lexer: Lexer<'s>) -> Self {
+        Self {
+            first: true,
+            end: lexer.end(),
+            tokens: lexer.peekable(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Parses ASCII `bytes`, see [`Lexer::from_bytes`].
+    pub fn from_bytes(bytes: &'s [u8]) -> Self {
+        Self {
+            first: true,
+            end: bytes.len(),
+            tokens: Lexer::from_bytes(bytes).peekable(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Parses all expressions, but instead of stopping at the first error it
+    /// skips to the next operator and continues, so all errors are reported at
+    /// once.
+    pub fn parse_recovering(mut self) -> Result<Vec<Expression>, Vec<Error>> {
+        let mut exprs = Vec::new();
+        let mut errors = Vec::new();
+        while let Some(expr) = self.next_expression() {
+            match expr {
+                Ok(expr) => exprs.push(expr),
+                Err(err) => {
+                    errors.push(err);
+                    self.recover(&mut errors);
+                }
+            }
         }
+        if errors.is_empty() {
+            Ok(exprs)
+        } else {
+            Err(errors)
+        }
+    }
+
+    // Skips to the next operator, keeping the errors of the lexer on the way.
+    fn recover(&mut self, errors: &mut Vec<Error>) {
+        while let Some(token) = self.tokens.next_if(|token| {
+            !matches!(
+                token,
+                Ok((_, Token::Add | Token::Sub | Token::Floor | Token::Jitter))
+            )
+        }) {
+            if let Err(err) = token {
+                errors.push(err);
+            }
+        }
+    }
+
+    /// Like [`Iterator::next`], but also returns the position in the text at
+    /// which the expression starts.
+    pub fn next_spanned(&mut self) -> Option<Result<(usize, Expression)>> {
+        if let Some(pending) = self.pending.pop_front() {
+            return Some(Ok(pending));
+        }
+        let index = match self.tokens.peek()? {
+            Ok((index, _)) => *index,
+            Err(_) => 0,
+        };
+        Some(Ok((index, ensure_ok!(self.next_expression())?)))
     }
 
     fn next_expression(&mut self) -> Option<Result<Expression>> {
+        if let Some((_, pending)) = self.pending.pop_front() {
+            return Some(Ok(pending));
+        }
         Some(Ok(match ensure_ok!(self.next_operator())? {
             Operator::Add => {
-                if matches!(self.tokens.peek(), Some(Ok((_, Token::Now)))) {
+                if let Some(Ok((index, Token::Now))) = self.tokens.peek() {
+                    let index = *index;
+                    self.tokens.next(); // discard peeked
+                    Expression::Now(index)
+                } else if let Some(Ok((index, Token::Epoch))) = self.tokens.peek() {
+                    let index = *index;
                     self.tokens.next(); // discard peeked
-                    Expression::Now
+                    Expression::Epoch(index)
                 } else {
                     let value = ensure_ok!(self.next_value())?;
                     let unit = ensure_ok!(self.next_unit())?;
-                    Expression::Add(value, unit)
+                    let value = ensure_ok!(self.next_repeat(value, unit))?;
+                    ensure_ok!(self.next_at_weekday(unit))?;
+                    if self.next_ago() {
+                        Expression::Sub(value, unit)
+                    } else {
+                        Expression::Add(value, unit)
+                    }
                 }
             }
             Operator::Sub => {
                 let value = ensure_ok!(self.next_value())?;
                 let unit = ensure_ok!(self.next_unit())?;
+                let value = ensure_ok!(self.next_repeat(value, unit))?;
+                ensure_ok!(self.next_at_weekday(unit))?;
+                if self.next_ago() {
+                    Expression::Add(value, unit)
+                } else {
+                    Expression::Sub(value, unit)
+                }
+            }
+            Operator::Implicit(index) => {
+                let value = ensure_ok!(self.next_value())?;
+                let unit = ensure_ok!(self.next_unit())?;
+                let value = ensure_ok!(self.next_repeat(value, unit))?;
+                ensure_ok!(self.next_at_weekday(unit))?;
+                if !self.next_ago() {
+                    return Some(Err(Error::InvalidFormat(
+                        index,
+                        TokenType::Operator,
+                        TokenType::Value,
+                    )));
+                }
                 Expression::Sub(value, unit)
             }
             Operator::Floor => {
+                if let Some(Ok((_, Token::Weekday(weekday)))) = self.tokens.peek() {
+                    let weekday = *weekday;
+                    self.tokens.next(); // discard peeked
+                    Expression::FloorWeekday(weekday)
+                } else if matches!(self.tokens.peek(), Some(Ok((_, Token::IsoYear)))) {
+                    self.tokens.next(); // discard peeked
+                    Expression::FloorIsoYear
+                } else if matches!(self.tokens.peek(), Some(Ok((_, Token::BusinessHours)))) {
+                    self.tokens.next(); // discard peeked
+                    Expression::FloorBusinessHours
+                } else if matches!(self.tokens.peek(), Some(Ok((_, Token::QuarterHour)))) {
+                    self.tokens.next(); // discard peeked
+                    Expression::FloorMinutes(15)
+                } else if matches!(self.tokens.peek(), Some(Ok((_, Token::HalfHour)))) {
+                    self.tokens.next(); // discard peeked
+                    Expression::FloorMinutes(30)
+                } else if let Some(Ok((_, Token::Value(n)))) = self.tokens.peek() {
+                    let n = *n;
+                    self.tokens.next(); // discard peeked
+                    let weekday = ensure_ok!(self.next_weekday())?;
+                    Expression::NthWeekday(n, weekday)
+                } else {
+                    let unit = ensure_ok!(self.next_unit())?;
+                    Expression::Floor(unit)
+                }
+            }
+            Operator::AssertFloor => {
                 let unit = ensure_ok!(self.next_unit())?;
-                Expression::Floor(unit)
+                Expression::AssertFloor(unit)
+            }
+            Operator::Jitter => {
+                let value = ensure_ok!(self.next_value())?;
+                let unit = ensure_ok!(self.next_unit())?;
+                Expression::Jitter(value, unit)
+            }
+            Operator::Weekday => {
+                let qualifier = self.next_qualifier();
+                let weekday = ensure_ok!(self.next_weekday())?;
+                Expression::Weekday(qualifier, weekday)
             }
         }))
     }
@@ -109,15 +380,28 @@ impl<'s> Parser<'s> {
             self.first = false;
             if matches!(
                 self.tokens.peek(),
-                Some(Ok((_, Token::Now | Token::Value(_))))
+                Some(Ok((_, Token::Now | Token::Epoch | Token::Value(_))))
             ) {
                 return Some(Ok(Operator::Add));
             }
+        } else if let Some(Ok((index, Token::Value(_)))) = self.tokens.peek() {
+            return Some(Ok(Operator::Implicit(*index)));
+        }
+        if matches!(
+            self.tokens.peek(),
+            Some(Ok((
+                _,
+                Token::This | Token::Next | Token::Last | Token::Weekday(_)
+            )))
+        ) {
+            return Some(Ok(Operator::Weekday));
         }
         match ensure_ok!(self.tokens.next())? {
             (_index, Token::Add) => Some(Ok(Operator::Add)),
             (_index, Token::Sub) => Some(Ok(Operator::Sub)),
             (_index, Token::Floor) => Some(Ok(Operator::Floor)),
+            (_index, Token::AssertFloor) => Some(Ok(Operator::AssertFloor)),
+            (_index, Token::Jitter) => Some(Ok(Operator::Jitter)),
             (index, token) => Some(Err(Error::InvalidFormat(
                 index,
                 TokenType::Operator,
@@ -125,22 +409,94 @@ impl<'s> Parser<'s> {
             ))),
         }
     }
+    // `*count` after a term, e.g. `+15m*4`, multiplies its value, so that is
+    // the same as `+60m`.
+    fn next_repeat(&mut self, value: u32, unit: Unit) -> Option<Result<u32>> {
+        let Some(Ok((index, _))) = self
+            .tokens
+            .next_if(|token| matches!(token, Ok((_, Token::Repeat))))
+        else {
+            return Some(Ok(value));
+        };
+        let count = ensure_ok!(self.next_value())?;
+        // years are added as months, which have to fit as well
+        let repeated = value.checked_mul(count).filter(|&repeated| {
+            unit != Unit::Year || repeated.checked_mul(MONTHS_PER_YEAR).is_some()
+        });
+        Some(repeated.ok_or(Error::OffsetTooLarge(
+            index,
+            unit,
+            u64::from(value) * u64::from(count),
+        )))
+    }
+    // `@weekday` after a week term, e.g. `+2w@mon`, moves to that weekday of
+    // the week the term lands in, with weeks starting on monday. This is
+    // queued to follow the term as a floor to monday and `this weekday`.
+    fn next_at_weekday(&mut self, unit: Unit) -> Option<Result<()>> {
+        if unit != Unit::Week {
+            return Some(Ok(()));
+        }
+        let Some(Ok((index, _))) = self
+            .tokens
+            .next_if(|token| matches!(token, Ok((_, Token::At))))
+        else {
+            return Some(Ok(()));
+        };
+        let weekday = ensure_ok!(self.next_weekday())?;
+        self.pending
+            .push_back((index, Expression::FloorWeekday(Weekday::Monday)));
+        self.pending
+            .push_back((index, Expression::Weekday(Qualifier::This, weekday)));
+        Some(Ok(()))
+    }
+    // `ago` negates the term preceding it
+    fn next_ago(&mut self) -> bool {
+        self.tokens
+            .next_if(|token| matches!(token, Ok((_, Token::Ago))))
+            .is_some()
+    }
+    fn next_qualifier(&mut self) -> Qualifier {
+        match self
+            .tokens
+            .next_if(|token| matches!(token, Ok((_, Token::This | Token::Next | Token::Last))))
+        {
+            Some(Ok((_, Token::Next))) => Qualifier::Next,
+            Some(Ok((_, Token::Last))) => Qualifier::Last,
+            _ => Qualifier::This,
+        }
+    }
+    fn next_weekday(&mut self) -> Option<Result<Weekday>> {
+        Some(match ensure_ok!(self.tokens.next()) {
+            Some((_index, Token::Weekday(weekday))) => Ok(weekday),
+            Some((index, token)) => Err(Error::InvalidFormat(
+                index,
+                TokenType::Weekday,
+                token.into(),
+            )),
+            None => Err(Error::UnexpectedEndOfInput(self.end, TokenType::Weekday)),
+        })
+    }
     fn next_value(&mut self) -> Option<Result<u32>> {
-        Some(match ensure_ok!(self.tokens.next())? {
-            (_index, Token::Value(value)) => Ok(value),
-            (index, token) => Err(Error::InvalidFormat(index, TokenType::Value, token.into())),
+        Some(match ensure_ok!(self.tokens.next()) {
+            Some((_index, Token::Value(value))) => Ok(value),
+            Some((index, token)) => {
+                Err(Error::InvalidFormat(index, TokenType::Value, token.into()))
+            }
+            None => Err(Error::UnexpectedEndOfInput(self.end, TokenType::Value)),
         })
     }
     fn next_unit(&mut self) -> Option<Result<Unit>> {
-        Some(match ensure_ok!(self.tokens.next())? {
-            (_index, Token::Year) => Ok(Unit::Year),
-            (_index, Token::Month) => Ok(Unit::Month),
-            (_index, Token::Week) => Ok(Unit::Week),
-            (_index, Token::Day) => Ok(Unit::Day),
-            (_index, Token::Hour) => Ok(Unit::Hour),
-            (_index, Token::Minute) => Ok(Unit::Minute),
-            (_index, Token::Second) => Ok(Unit::Second),
-            (index, token) => Err(Error::InvalidFormat(index, TokenType::Unit, token.into())),
+        Some(match ensure_ok!(self.tokens.next()) {
+            Some((_index, Token::Year)) => Ok(Unit::Year),
+            Some((_index, Token::Month)) => Ok(Unit::Month),
+            Some((_index, Token::Week)) => Ok(Unit::Week),
+            Some((_index, Token::Day)) => Ok(Unit::Day),
+            Some((_index, Token::Hour)) => Ok(Unit::Hour),
+            Some((_index, Token::Minute)) => Ok(Unit::Minute),
+            Some((_index, Token::Second)) => Ok(Unit::Second),
+            Some((_index, Token::Millisecond)) => Ok(Unit::Millisecond),
+            Some((index, token)) => Err(Error::InvalidFormat(index, TokenType::Unit, token.into())),
+            None => Err(Error::UnexpectedEndOfInput(self.end, TokenType::Unit)),
         })
     }
 }
@@ -153,6 +509,55 @@ impl Iterator for Parser<'_> {
     }
 }
 
+/// Merges consecutive terms of the same unit into one, e.g. `+1d+2d` into
+/// `+3d`, and drops terms that add nothing. Any other expression, like a floor,
+/// is a barrier that terms are not merged across. Merged months and years
+/// clamp the day once, instead of after every term.
+pub fn normalize(exprs: &mut Vec<Expression>) {
+    let mut normalized = Vec::with_capacity(exprs.len());
+    let mut pending: Option<(i64, Unit)> = None;
+    for expr in exprs.drain(..) {
+        let Some((value, unit)) = signed(expr) else {
+            normalized.extend(pending.take().and_then(term));
+            normalized.push(expr);
+            continue;
+        };
+        pending = match pending {
+            Some((sum, pending_unit))
+                if pending_unit == unit && term((sum + value, unit)).is_some() =>
+            {
+                Some((sum + value, unit))
+            }
+            // a sum of zero is dropped as well
+            Some((sum, pending_unit)) if pending_unit == unit && sum + value == 0 => None,
+            _ => {
+                normalized.extend(pending.and_then(term));
+                Some((value, unit))
+            }
+        };
+    }
+    normalized.extend(pending.and_then(term));
+    *exprs = normalized;
+}
+
+fn signed(expr: Expression) -> Option<(i64, Unit)> {
+    match expr {
+        Expression::Add(value, unit) => Some((value.into(), unit)),
+        Expression::Sub(value, unit) => Some((-i64::from(value), unit)),
+        _ => None,
+    }
+}
+
+// The term for a signed value, or `None` when it is zero or too large.
+fn term((value, unit): (i64, Unit)) -> Option<Expression> {
+    let magnitude = u32::try_from(value.unsigned_abs()).ok()?;
+    match value.signum() {
+        1 => Some(Expression::Add(magnitude, unit)),
+        -1 => Some(Expression::Sub(magnitude, unit)),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -174,19 +579,28 @@ mod test {
 
     #[test]
     fn now() {
-        parse_eq!("now", Ok(vec![Expression::Now]));
+        parse_eq!("now", Ok(vec![Expression::Now(0)]));
     }
 
     #[test]
     fn add_now() {
-        parse_eq!("+now", Ok(vec![Expression::Now]));
+        parse_eq!("+now", Ok(vec![Expression::Now(1)]));
+    }
+
+    #[test]
+    fn epoch() {
+        parse_eq!("epoch", Ok(vec![Expression::Epoch(0)]));
+        parse_eq!(
+            "1d ago+epoch",
+            Ok(vec![Expression::Sub(1, Unit::Day), Expression::Epoch(7)])
+        );
     }
 
     #[test]
     fn now_add_year() {
         parse_eq!(
             "now+1y",
-            Ok(vec![Expression::Now, Expression::Add(1, Unit::Year)])
+            Ok(vec![Expression::Now(0), Expression::Add(1, Unit::Year)])
         );
     }
 
@@ -194,7 +608,7 @@ mod test {
     fn add_seconds_now() {
         parse_eq!(
             "+1s+now",
-            Ok(vec![Expression::Add(1, Unit::Second), Expression::Now])
+            Ok(vec![Expression::Add(1, Unit::Second), Expression::Now(4)])
         );
     }
 
@@ -202,7 +616,7 @@ mod test {
     fn sub_day_now() {
         parse_eq!(
             "-5d+now",
-            Ok(vec![Expression::Sub(5, Unit::Day), Expression::Now])
+            Ok(vec![Expression::Sub(5, Unit::Day), Expression::Now(4)])
         );
     }
 
@@ -210,7 +624,90 @@ mod test {
     fn floor_week() {
         parse_eq!(
             "now/w",
-            Ok(vec![Expression::Now, Expression::Floor(Unit::Week)])
+            Ok(vec![Expression::Now(0), Expression::Floor(Unit::Week)])
+        );
+    }
+
+    #[test]
+    fn assert_floor() {
+        parse_eq!(
+            "now/d|d",
+            Ok(vec![
+                Expression::Now(0),
+                Expression::Floor(Unit::Day),
+                Expression::AssertFloor(Unit::Day)
+            ])
+        );
+        parse_eq!(
+            "now|mon",
+            Err(Error::InvalidFormat(4, TokenType::Unit, TokenType::Weekday))
+        );
+    }
+
+    #[test]
+    fn nth_weekday() {
+        parse_eq!(
+            "now+1M/3thu",
+            Ok(vec![
+                Expression::Now(0),
+                Expression::Add(1, Unit::Month),
+                Expression::NthWeekday(3, Weekday::Thursday)
+            ])
+        );
+        parse_eq!(
+            "now/3d",
+            Err(Error::InvalidFormat(5, TokenType::Weekday, TokenType::Day))
+        );
+    }
+
+    #[test]
+    fn repeat() {
+        parse_eq!(
+            "now+15m*4-1d*2 ago+2w*3@mon",
+            Ok(vec![
+                Expression::Now(0),
+                Expression::Add(60, Unit::Minute),
+                Expression::Add(2, Unit::Day),
+                Expression::Add(6, Unit::Week),
+                Expression::FloorWeekday(Weekday::Monday),
+                Expression::Weekday(Qualifier::This, Weekday::Monday)
+            ])
+        );
+        parse_eq!(
+            "now+2y*4294967295",
+            Err(Error::OffsetTooLarge(6, Unit::Year, 8_589_934_590))
+        );
+        parse_eq!(
+            "now+1d*d",
+            Err(Error::InvalidFormat(7, TokenType::Value, TokenType::Day))
+        );
+        parse_eq!(
+            "now*2",
+            Err(Error::InvalidFormat(
+                3,
+                TokenType::Operator,
+                TokenType::Repeat
+            ))
+        );
+    }
+
+    #[test]
+    fn jitter() {
+        parse_eq!(
+            "now+1h~5m",
+            Ok(vec![
+                Expression::Now(0),
+                Expression::Add(1, Unit::Hour),
+                Expression::Jitter(5, Unit::Minute)
+            ])
+        );
+        parse_eq!(
+            "now~h",
+            Err(Error::InvalidFormat(4, TokenType::Value, TokenType::Hour))
+        );
+        parse_eq!(
+            "now~",
+            Err(Error::UnexpectedEndOfInput(4, TokenType::Value))
         );
     }
 
@@ -219,7 +716,7 @@ mod test {
         parse_eq!(
             "now+0y-0m+0s",
             Ok(vec![
-                Expression::Now,
+                Expression::Now(0),
                 Expression::Add(0, Unit::Year),
                 Expression::Sub(0, Unit::Minute),
                 Expression::Add(0, Unit::Second)
@@ -227,13 +724,332 @@ mod test {
         );
     }
 
+    #[test]
+    fn dangling_operator() {
+        parse_eq!(
+            "now+",
+            Err(Error::UnexpectedEndOfInput(4, TokenType::Value))
+        );
+        parse_eq!(
+            "now+1",
+            Err(Error::UnexpectedEndOfInput(5, TokenType::Unit))
+        );
+        parse_eq!("now/", Err(Error::UnexpectedEndOfInput(4, TokenType::Unit)));
+        parse_eq!(
+            "now+1d*",
+            Err(Error::UnexpectedEndOfInput(7, TokenType::Value))
+        );
+        parse_eq!(
+            "now next",
+            Err(Error::UnexpectedEndOfInput(8, TokenType::Weekday))
+        );
+    }
+
     #[test]
     fn day_sub_now() {
         parse_matches!("1d-now", Err(Error::InvalidFormat(3, _, _)));
     }
 
+    #[test]
+    fn ago() {
+        parse_eq!(
+            "now 3d ago",
+            Ok(vec![Expression::Now(0), Expression::Sub(3, Unit::Day)])
+        );
+        parse_eq!(
+            "now+1y 3d ago",
+            Ok(vec![
+                Expression::Now(0),
+                Expression::Add(1, Unit::Year),
+                Expression::Sub(3, Unit::Day)
+            ])
+        );
+        parse_eq!(
+            "now-3d ago",
+            Ok(vec![Expression::Now(0), Expression::Add(3, Unit::Day)])
+        );
+        parse_eq!(
+            "3d ago now",
+            Err(Error::InvalidFormat(7, TokenType::Operator, TokenType::Now))
+        );
+    }
+
+    #[test]
+    fn term_without_operator() {
+        parse_eq!(
+            "now 3d",
+            Err(Error::InvalidFormat(
+                4,
+                TokenType::Operator,
+                TokenType::Value
+            ))
+        );
+        parse_eq!(
+            "now ago",
+            Err(Error::InvalidFormat(4, TokenType::Operator, TokenType::Ago))
+        );
+    }
+
+    #[test]
+    fn weekday() {
+        parse_eq!(
+            "now next mon",
+            Ok(vec![
+                Expression::Now(0),
+                Expression::Weekday(Qualifier::Next, Weekday::Monday)
+            ])
+        );
+        parse_eq!(
+            "now+1w last fri/d sun this sat",
+            Ok(vec![
+                Expression::Now(0),
+                Expression::Add(1, Unit::Week),
+                Expression::Weekday(Qualifier::Last, Weekday::Friday),
+                Expression::Floor(Unit::Day),
+                Expression::Weekday(Qualifier::This, Weekday::Sunday),
+                Expression::Weekday(Qualifier::This, Weekday::Saturday)
+            ])
+        );
+        parse_eq!(
+            "now next 1d",
+            Err(Error::InvalidFormat(
+                9,
+                TokenType::Weekday,
+                TokenType::Value
+            ))
+        );
+        parse_eq!(
+            "now+mon",
+            Err(Error::InvalidFormat(
+                4,
+                TokenType::Value,
+                TokenType::Weekday
+            ))
+        );
+    }
+
+    #[test]
+    fn floor_weekday() {
+        parse_eq!(
+            "now/mon",
+            Ok(vec![
+                Expression::Now(0),
+                Expression::FloorWeekday(Weekday::Monday)
+            ])
+        );
+        parse_eq!(
+            "now/next",
+            Err(Error::InvalidFormat(4, TokenType::Unit, TokenType::Next))
+        );
+    }
+
+    #[test]
+    fn week_at_weekday() {
+        parse_eq!(
+            "now+2w@mon",
+            Ok(vec![
+                Expression::Now(0),
+                Expression::Add(2, Unit::Week),
+                Expression::FloorWeekday(Weekday::Monday),
+                Expression::Weekday(Qualifier::This, Weekday::Monday),
+            ])
+        );
+        parse_eq!(
+            "now 1w@fri ago/d",
+            Ok(vec![
+                Expression::Now(0),
+                Expression::Sub(1, Unit::Week),
+                Expression::FloorWeekday(Weekday::Monday),
+                Expression::Weekday(Qualifier::This, Weekday::Friday),
+                Expression::Floor(Unit::Day),
+            ])
+        );
+        assert_eq!(
+            std::iter::from_fn({
+                let mut parser = Parser::new("now-1w@sun");
+                move || parser.next_spanned()
+            })
+            .collect::<Result<Vec<_>>>(),
+            Ok(vec![
+                (0, Expression::Now(0)),
+                (3, Expression::Sub(1, Unit::Week)),
+                (6, Expression::FloorWeekday(Weekday::Monday)),
+                (6, Expression::Weekday(Qualifier::This, Weekday::Sunday)),
+            ])
+        );
+        parse_eq!(
+            "now+1d@fri",
+            Err(Error::InvalidFormat(6, TokenType::Operator, TokenType::At))
+        );
+        parse_eq!(
+            "now+1w@next",
+            Err(Error::InvalidFormat(7, TokenType::Weekday, TokenType::Next))
+        );
+    }
+
+    #[test]
+    fn display_weekday() {
+        assert_eq!(
+            Expression::Weekday(Qualifier::Next, Weekday::Monday).to_string(),
+            "next monday"
+        );
+        assert_eq!(
+            Expression::Weekday(Qualifier::This, Weekday::Sunday).to_string(),
+            "this sunday"
+        );
+        assert_eq!(
+            Expression::FloorWeekday(Weekday::Friday).to_string(),
+            "floor friday"
+        );
+    }
+
+    #[test]
+    fn display_operator() {
+        assert_eq!(Operator::Add.to_string(), "+");
+        assert_eq!(Operator::Sub.to_string(), "-");
+        assert_eq!(Operator::Floor.to_string(), "/");
+        assert_eq!(Operator::Implicit(0).to_string(), "");
+    }
+
+    #[test]
+    fn spanned() {
+        let mut parser = Parser::new(" now+1d 2h ago/d");
+        assert_eq!(parser.next_spanned(), Some(Ok((1, Expression::Now(1)))));
+        assert_eq!(
+            parser.next_spanned(),
+            Some(Ok((4, Expression::Add(1, Unit::Day))))
+        );
+        assert_eq!(
+            parser.next_spanned(),
+            Some(Ok((8, Expression::Sub(2, Unit::Hour))))
+        );
+        assert_eq!(
+            parser.next_spanned(),
+            Some(Ok((14, Expression::Floor(Unit::Day))))
+        );
+        assert_eq!(parser.next_spanned(), None);
+    }
+
     #[test]
     fn no_input() {
         parse_eq!("", Ok(vec![]));
     }
+
+    #[test]
+    fn clone_partially_consumed() {
+        let mut parser = Parser::new("now+1d-2h/d");
+        assert_eq!(parser.next(), Some(Ok(Expression::Now(0))));
+
+        let cloned = parser.clone();
+        assert_eq!(
+            parser.collect::<Result<Vec<_>>>(),
+            cloned.collect::<Result<Vec<_>>>()
+        );
+    }
+
+    #[test]
+    fn unit_order() {
+        assert!(Unit::Year < Unit::Month);
+        let mut units = vec![Unit::Second, Unit::Day, Unit::Year, Unit::Week];
+        units.sort();
+        assert_eq!(units, vec![Unit::Year, Unit::Week, Unit::Day, Unit::Second]);
+    }
+
+    fn normalized(input: &str) -> Vec<Expression> {
+        let mut exprs = Parser::new(input)
+            .collect::<Result<Vec<_>>>()
+            .expect("input invalid");
+        normalize(&mut exprs);
+        exprs
+    }
+
+    #[test]
+    fn normalize_merges() {
+        assert_eq!(
+            normalized("now+1d+2d"),
+            vec![Expression::Now(0), Expression::Add(3, Unit::Day)]
+        );
+        assert_eq!(
+            normalized("now+1d-3d+1h 2h ago"),
+            vec![
+                Expression::Now(0),
+                Expression::Sub(2, Unit::Day),
+                Expression::Sub(1, Unit::Hour),
+            ]
+        );
+        assert_eq!(
+            normalized("now+1d/d+1d"),
+            vec![
+                Expression::Now(0),
+                Expression::Add(1, Unit::Day),
+                Expression::Floor(Unit::Day),
+                Expression::Add(1, Unit::Day),
+            ],
+            "floors are barriers"
+        );
+        assert_eq!(
+            normalized("now+4294967295s+1s"),
+            vec![
+                Expression::Now(0),
+                Expression::Add(u32::MAX, Unit::Second),
+                Expression::Add(1, Unit::Second),
+            ],
+            "sums that do not fit are kept apart"
+        );
+    }
+
+    #[test]
+    fn normalize_drops_zeros() {
+        assert_eq!(normalized("now+0d"), vec![Expression::Now(0)]);
+        assert_eq!(normalized("now+1d-1d"), vec![Expression::Now(0)]);
+        assert_eq!(
+            normalized("now+2h-2h+1m"),
+            vec![Expression::Now(0), Expression::Add(1, Unit::Minute)]
+        );
+        assert_eq!(
+            normalized("now+1d 1d ago/d"),
+            vec![Expression::Now(0), Expression::Floor(Unit::Day)]
+        );
+    }
+
+    #[test]
+    fn recovering() {
+        assert_eq!(
+            Parser::new("now+1x-2d/z").parse_recovering(),
+            Err(vec![
+                Error::UnexpectedCharacter(5, 'x'),
+                Error::UnexpectedCharacter(10, 'z'),
+            ])
+        );
+        assert_eq!(
+            Parser::new("now+-1d ago/d+3 4294967296s").parse_recovering(),
+            Err(vec![
+                Error::InvalidFormat(4, TokenType::Value, TokenType::Sub),
+                Error::InvalidNumber(16, 26),
+            ])
+        );
+        assert_eq!(
+            Parser::new("now+1d/d").parse_recovering(),
+            Ok(vec![
+                Expression::Now(0),
+                Expression::Add(1, Unit::Day),
+                Expression::Floor(Unit::Day),
+            ])
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let exprs = parse("now-1w@sun+3d ago/d next fri/3thu|h/Y/bh").expect("input invalid");
+        let json = serde_json::to_string(&exprs).expect("serializing failed");
+        assert_eq!(
+            serde_json::from_str::<Vec<Expression>>(&json).map_err(|err| err.to_string()),
+            Ok(exprs)
+        );
+        assert_eq!(
+            serde_json::to_string(&Expression::Add(5, Unit::Day)).ok(),
+            Some(r#"{"Add":[5,"Day"]}"#.to_string())
+        );
+    }
 }
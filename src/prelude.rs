@@ -0,0 +1,16 @@
+//! The common items, for a single glob import.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use relative_time_str::prelude::*;
+//!
+//! let time = parse_str_with_now("now+1d", Duration::ZERO);
+//! assert_eq!(time, Ok(Duration::from_secs(24 * 60 * 60)));
+//! ```
+
+pub use crate::{
+    Error, ParseOptions, RelativeTime, RelativeTimeNow, Result, parse_str, parse_str_with_now,
+    parse_str_with_options,
+    parser::{Expression, Unit},
+};
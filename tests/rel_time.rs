@@ -0,0 +1,10 @@
+// only the test cases use the crate and its dependencies
+#![allow(unused_crate_dependencies)]
+
+#[test]
+fn rel_time() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/ui/pass.rs");
+    cases.compile_fail("tests/ui/missing_now.rs");
+    cases.compile_fail("tests/ui/missing_unit.rs");
+}
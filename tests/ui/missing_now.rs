@@ -0,0 +1,10 @@
+// the dependencies of the crate differ with its features
+#![allow(unused_crate_dependencies)]
+
+use std::time::Duration;
+
+use relative_time_str::rel_time;
+
+fn main() {
+    let _ = rel_time!("+1d", Duration::ZERO);
+}
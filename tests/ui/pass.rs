@@ -0,0 +1,17 @@
+// the dependencies of the crate differ with its features
+#![allow(unused_crate_dependencies)]
+
+use std::time::Duration;
+
+use relative_time_str::rel_time;
+
+fn main() {
+    assert_eq!(
+        rel_time!("now + 1d - 2h", Duration::ZERO),
+        Ok(Duration::from_secs(22 * 60 * 60))
+    );
+    assert_eq!(
+        rel_time!("now 1s ago", Duration::ZERO),
+        Err(relative_time_str::Error::NegativeDuration)
+    );
+}